@@ -0,0 +1,196 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use tracing::{debug, instrument, warn};
+
+use crate::datfile::{DatFile, FortuneFile, dat_path_for_text};
+use crate::error::{Error, Result};
+use crate::hash::fnv1a;
+use crate::strfile_builder::{BuildOptions, build_dat_from_text};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AutoIndex {
+    #[default]
+    Off,
+    Memory,
+    Write,
+}
+
+impl AutoIndex {
+    pub fn enabled(self) -> bool {
+        !matches!(self, Self::Off)
+    }
+}
+
+impl FromStr for AutoIndex {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "off" => Ok(Self::Off),
+            "memory" => Ok(Self::Memory),
+            "write" => Ok(Self::Write),
+            other => Err(Error::InvalidArgument(format!(
+                "unknown auto-index mode '{other}' (expected off, memory or write)"
+            ))),
+        }
+    }
+}
+
+// Names and extensions that show up next to corpora but are never one.
+const NON_CORPUS_NAMES: &[&str] = &[
+    "authors",
+    "changelog",
+    "changes",
+    "contributing",
+    "copying",
+    "install",
+    "licence",
+    "license",
+    "makefile",
+    "news",
+    "notice",
+    "readme",
+    "todo",
+];
+const NON_CORPUS_EXTENSIONS: &[&str] = &[
+    "bak", "bz2", "c", "cfg", "csv", "gif", "gz", "h", "htm", "html", "in", "ini", "jpeg", "jpg",
+    "json", "md", "mk", "orig", "pdf", "png", "py", "rej", "rs", "rst", "sh", "svg", "swp", "tar",
+    "toml", "xml", "xz", "yaml", "yml", "zip",
+];
+
+pub fn is_indexable(text_path: &Path, mode: AutoIndex) -> bool {
+    mode.enabled() || dat_path_for_text(text_path).is_file()
+}
+
+// Directory walks only auto-index files that look like a corpus, so a
+// README or Makefile next to the fortunes is not served (or written a .dat).
+// Files named explicitly on the command line are indexed regardless.
+pub fn is_indexable_in_dir(text_path: &Path, mode: AutoIndex) -> bool {
+    dat_path_for_text(text_path).is_file() || (mode.enabled() && looks_like_corpus(text_path))
+}
+
+fn looks_like_corpus(text_path: &Path) -> bool {
+    let Some(name) = text_path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    if name.ends_with('~') {
+        return false;
+    }
+    let name = name.to_ascii_lowercase();
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((&name, ""));
+    !NON_CORPUS_NAMES.contains(&stem) && !NON_CORPUS_EXTENSIONS.contains(&extension)
+}
+
+#[instrument(skip_all, fields(path = %text_path.display(), ?mode))]
+pub fn open_fortune_file(text_path: &Path, mode: AutoIndex) -> Result<FortuneFile> {
+    if !mode.enabled() {
+        return FortuneFile::open(text_path);
+    }
+
+    let sibling = dat_path_for_text(text_path);
+    let text_mtime = modified(text_path).map_err(Error::io("reading metadata for", text_path))?;
+    if is_fresh(&sibling, text_mtime) {
+        return FortuneFile::open(text_path);
+    }
+
+    let cached = cached_dat_path(text_path);
+    if let Some(cached) = cached.as_deref()
+        && is_fresh(cached, text_mtime)
+    {
+        debug!(dat = %cached.display(), "using cached auto-index");
+        return open_with_dat(text_path, cached);
+    }
+
+    let bytes = fs::read(text_path).map_err(Error::io("reading fortune text", text_path))?;
+    // A stale index still tells us which delimiter the corpus was built with.
+    let delimiter = DatFile::read_from_path(&sibling)
+        .map(|dat| dat.header.delim)
+        .unwrap_or(BuildOptions::default().delimiter);
+    let options = BuildOptions {
+        delimiter,
+        ..BuildOptions::default()
+    };
+    let (dat, stats) = build_dat_from_text(&bytes, options)?;
+    debug!(records = stats.record_count, "built index in memory");
+
+    let dat_path = if mode == AutoIndex::Write {
+        persist_index(&dat, &sibling, cached.as_deref())
+    } else {
+        None
+    };
+    FortuneFile::from_parts(text_path, dat_path, dat, bytes)
+}
+
+pub fn cached_dat_path(text_path: &Path) -> Option<PathBuf> {
+    let absolute = fs::canonicalize(text_path).ok()?;
+    let name = absolute.file_name()?.to_string_lossy().into_owned();
+    let key = fnv1a(absolute.as_os_str().as_encoded_bytes());
+    Some(
+        cache_dir()?
+            .join("dat")
+            .join(format!("{key:016x}-{name}.dat")),
+    )
+}
+
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|v| !v.is_empty())
+                .map(|home| PathBuf::from(home).join(".cache"))
+        })?;
+    Some(base.join("rustune"))
+}
+
+fn open_with_dat(text_path: &Path, dat_path: &Path) -> Result<FortuneFile> {
+    let dat = DatFile::read_from_path(dat_path)?;
+    let bytes = fs::read(text_path).map_err(Error::io("reading fortune text", text_path))?;
+    FortuneFile::from_parts(text_path, Some(dat_path.to_path_buf()), dat, bytes)
+}
+
+// Returns where the index was written, if anywhere.
+fn persist_index(dat: &DatFile, sibling: &Path, cached: Option<&Path>) -> Option<PathBuf> {
+    match dat.write_to_path(sibling) {
+        Ok(()) => {
+            debug!(dat = %sibling.display(), "wrote auto-index next to corpus");
+            return Some(sibling.to_path_buf());
+        }
+        Err(err) => debug!(dat = %sibling.display(), "cannot write sibling index: {err}"),
+    }
+
+    let Some(cached) = cached else {
+        warn!("no writable location for auto-index; keeping it in memory");
+        return None;
+    };
+    let written = cached
+        .parent()
+        .map(|dir| fs::create_dir_all(dir).map_err(Error::io("creating", dir)))
+        .transpose()
+        .and_then(|_| dat.write_to_path(cached));
+    match written {
+        Ok(()) => {
+            debug!(dat = %cached.display(), "wrote auto-index to cache");
+            Some(cached.to_path_buf())
+        }
+        Err(err) => {
+            warn!(dat = %cached.display(), "failed caching auto-index: {err}");
+            None
+        }
+    }
+}
+
+fn is_fresh(dat_path: &Path, text_mtime: SystemTime) -> bool {
+    modified(dat_path)
+        .map(|dat_mtime| dat_mtime >= text_mtime)
+        .unwrap_or(false)
+}
+
+fn modified(path: &Path) -> std::io::Result<SystemTime> {
+    fs::metadata(path)?.modified()
+}
@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::{ArgAction, ArgGroup, Args as ClapArgs, Parser, Subcommand};
+use regex::Regex;
+use tracing::{debug, instrument};
+
+use rustune::corpus::{
+    REST_BUCKET, SplitRule, merge_records, read_records, split_records, write_corpus,
+};
+use rustune::error::{Error, exit_code_for};
+use rustune::logging::init_logging;
+use rustune::strfile_builder::parse_delimiter;
+
+#[derive(Debug, Parser)]
+#[command(name = "fortune-corpus")]
+#[command(about = "Merge and split fortune corpora")]
+struct Args {
+    #[arg(long = "verbose", action = ArgAction::SetTrue, global = true)]
+    verbose: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    Merge(MergeArgs),
+    Split(SplitArgs),
+}
+
+#[derive(Debug, ClapArgs)]
+struct MergeArgs {
+    #[arg(short = 'O', long = "output", value_name = "FILE")]
+    output: PathBuf,
+    #[arg(short = 'c', long = "delimiter", default_value = "%")]
+    delimiter: String,
+    #[arg(long = "keep-duplicates", action = ArgAction::SetTrue)]
+    keep_duplicates: bool,
+    #[arg(value_name = "INPUT", required = true)]
+    inputs: Vec<PathBuf>,
+}
+
+#[derive(Debug, ClapArgs)]
+#[command(group(ArgGroup::new("rule").required(true).args(["regex", "lengths", "count"])))]
+struct SplitArgs {
+    #[arg(short = 'O', long = "output-dir", value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+    #[arg(short = 'c', long = "delimiter")]
+    delimiter: Option<String>,
+    #[arg(long = "regex", value_name = "NAME=PATTERN")]
+    regex: Vec<String>,
+    #[arg(long = "lengths", value_name = "N,...", value_delimiter = ',')]
+    lengths: Vec<usize>,
+    #[arg(long = "count", value_name = "N")]
+    count: Option<usize>,
+    #[arg(value_name = "INPUT")]
+    input: PathBuf,
+}
+
+fn main() {
+    let args = Args::parse();
+    init_logging(args.verbose, "warn,rustune=info,fortune_corpus=info");
+    if let Err(err) = run(args) {
+        eprintln!("fortune-corpus: {err:#}");
+        std::process::exit(exit_code_for(err.as_ref()));
+    }
+}
+
+#[instrument(skip_all)]
+fn run(args: Args) -> Result<()> {
+    match args.command {
+        Command::Merge(merge) => run_merge(merge),
+        Command::Split(split) => run_split(split),
+    }
+}
+
+fn run_merge(args: MergeArgs) -> Result<()> {
+    let delimiter = parse_delimiter(&args.delimiter)?;
+    let mut inputs = Vec::with_capacity(args.inputs.len());
+    for path in &args.inputs {
+        inputs.push(read_records(path)?);
+    }
+    let (records, dropped) = merge_records(inputs, !args.keep_duplicates);
+    let stats = write_corpus(&args.output, &records, delimiter)?;
+    println!(
+        "\"{}\" created from {} corpora\n{} strings\n{dropped} duplicates dropped",
+        args.output.display(),
+        args.inputs.len(),
+        stats.record_count
+    );
+    Ok(())
+}
+
+fn run_split(args: SplitArgs) -> Result<()> {
+    let rule = split_rule(&args)?;
+    let corpus = read_records(&args.input)?;
+    let delimiter = match &args.delimiter {
+        Some(value) => parse_delimiter(value)?,
+        None => corpus.delimiter,
+    };
+    let output_dir = args.output_dir.clone().unwrap_or_else(|| {
+        args.input
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    });
+    let stem = args
+        .input
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "corpus".into());
+
+    let buckets = split_records(corpus.records, &rule);
+    debug!(buckets = buckets.len(), "split corpus");
+    for bucket in buckets {
+        let path = output_dir.join(format!("{stem}-{}", bucket.name));
+        let stats = write_corpus(&path, &bucket.records, delimiter)?;
+        println!(
+            "\"{}\" created\n{} strings",
+            path.display(),
+            stats.record_count
+        );
+    }
+    Ok(())
+}
+
+fn split_rule(args: &SplitArgs) -> rustune::Result<SplitRule> {
+    if let Some(count) = args.count {
+        if count == 0 {
+            return Err(Error::InvalidArgument("--count must be at least 1".into()));
+        }
+        return Ok(SplitRule::Count(count));
+    }
+    if !args.lengths.is_empty() {
+        let mut limits = args.lengths.clone();
+        limits.sort_unstable();
+        limits.dedup();
+        return Ok(SplitRule::Length(limits));
+    }
+    let mut patterns = Vec::with_capacity(args.regex.len());
+    for spec in &args.regex {
+        let (name, pattern) = spec.split_once('=').ok_or_else(|| {
+            Error::InvalidArgument(format!("--regex expects NAME=PATTERN, got '{spec}'"))
+        })?;
+        if name.is_empty() || name.contains(std::path::is_separator) {
+            return Err(Error::InvalidArgument(format!(
+                "invalid bucket name '{name}' in --regex"
+            )));
+        }
+        // Every bucket becomes `{stem}-{name}`, so a repeated name would
+        // overwrite an earlier bucket's file.
+        if name == REST_BUCKET {
+            return Err(Error::InvalidArgument(format!(
+                "bucket name '{REST_BUCKET}' is reserved for unmatched records"
+            )));
+        }
+        if patterns.iter().any(|(seen, _)| seen == name) {
+            return Err(Error::InvalidArgument(format!(
+                "bucket name '{name}' is given more than once in --regex"
+            )));
+        }
+        patterns.push((name.to_string(), Regex::new(pattern)?));
+    }
+    Ok(SplitRule::Regex(patterns))
+}
@@ -0,0 +1,116 @@
+use std::io::{self, Write};
+
+use anyhow::Result;
+use clap::{ArgAction, Parser};
+use tracing::{debug, info, instrument};
+
+use rustune::auto_index::AutoIndex;
+use rustune::datfile::LengthFilter;
+use rustune::dedupe::{
+    DedupeConfig, MatchKind, RecordRef, find_duplicates, removals_by_path, rewrite_without,
+};
+use rustune::discovery::{DiscoveryConfig, discover_weighted_sources};
+use rustune::error::{Error, exit_code_for};
+use rustune::fortune_engine::{LoadedSource, load_sources};
+use rustune::logging::init_logging;
+use rustune::sources::parse_source_specs;
+
+#[derive(Debug, Parser)]
+#[command(name = "fortune-dedupe")]
+#[command(about = "Report and remove duplicate fortunes across corpora")]
+struct Args {
+    #[arg(long = "threshold", default_value_t = 0.8)]
+    threshold: f64,
+    #[arg(long = "exact-only", action = ArgAction::SetTrue)]
+    exact_only: bool,
+    #[arg(long = "rewrite", action = ArgAction::SetTrue)]
+    rewrite: bool,
+    #[arg(short = 'a', long = "all", action = ArgAction::SetTrue)]
+    allow_any: bool,
+    #[arg(short = 'o', long = "offensive", action = ArgAction::SetTrue)]
+    offensive_only: bool,
+    #[arg(long = "verbose", action = ArgAction::SetTrue)]
+    verbose: bool,
+    #[arg(value_name = "SOURCE")]
+    sources: Vec<String>,
+}
+
+fn main() {
+    let args = Args::parse();
+    init_logging(args.verbose, "warn,rustune=info,fortune_dedupe=info");
+    if let Err(err) = run(args) {
+        eprintln!("fortune-dedupe: {err:#}");
+        std::process::exit(exit_code_for(err.as_ref()));
+    }
+}
+
+#[instrument(skip_all)]
+fn run(args: Args) -> Result<()> {
+    if !(0.0..=1.0).contains(&args.threshold) {
+        return Err(Error::InvalidArgument(format!(
+            "--threshold must be between 0 and 1, got {}",
+            args.threshold
+        ))
+        .into());
+    }
+    let specs = parse_source_specs(&args.sources)?;
+    let config = DiscoveryConfig {
+        allow_any: args.allow_any,
+        offensive_only: args.offensive_only,
+        auto_index: AutoIndex::Off,
+        ..DiscoveryConfig::default()
+    };
+    let discovered = discover_weighted_sources(&specs, &config)?;
+    let sources = load_sources(&discovered, LengthFilter::Any, AutoIndex::Off)?;
+    let dedupe = DedupeConfig {
+        threshold: args.threshold,
+        fuzzy: !args.exact_only,
+        ..DedupeConfig::default()
+    };
+    let groups = find_duplicates(&sources, &dedupe)?;
+
+    let mut out = io::stdout().lock();
+    let mut total = 0;
+    for group in &groups {
+        writeln!(out, "duplicates of {}", describe(&sources, group.keep)?)?;
+        for dup in &group.duplicates {
+            let label = match dup.kind {
+                MatchKind::Exact => "exact".to_string(),
+                MatchKind::Fuzzy => format!("{:.2}", dup.similarity),
+            };
+            writeln!(out, "  {label:<5} {}", describe(&sources, dup.record)?)?;
+            total += 1;
+        }
+    }
+    writeln!(
+        out,
+        "{total} duplicate record(s) in {} group(s)",
+        groups.len()
+    )?;
+    debug!(groups = groups.len(), total, "dedupe finished");
+
+    if args.rewrite {
+        for (path, remove) in removals_by_path(&sources, &groups) {
+            let kept = rewrite_without(&path, &remove)?;
+            info!(path = %path.display(), removed = remove.len(), kept, "rewrote corpus");
+            writeln!(
+                out,
+                "rewrote {}: removed {}, kept {kept}",
+                path.display(),
+                remove.len()
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn describe(sources: &[LoadedSource], record: RecordRef) -> Result<String> {
+    let source = &sources[record.source].source;
+    let text = source.record_text_lossy(record.record)?;
+    let first_line = text.lines().next().unwrap_or_default();
+    Ok(format!(
+        "{} #{}: {first_line}",
+        source.display_name(),
+        record.record
+    ))
+}
@@ -0,0 +1,87 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{ArgAction, Parser};
+use tracing::{debug, instrument};
+
+use rustune::auto_index::AutoIndex;
+use rustune::discovery::{DiscoveryConfig, discover_weighted_sources};
+use rustune::error::{Error, exit_code_for};
+use rustune::export::{ExportFormat, export_records, open_export_file, write_records};
+use rustune::logging::init_logging;
+use rustune::sources::parse_source_specs;
+
+#[derive(Debug, Parser)]
+#[command(name = "fortune-export")]
+#[command(about = "Export fortune corpora as NDJSON, CSV or Markdown")]
+struct Args {
+    #[arg(long = "format", value_name = "FORMAT", default_value = "ndjson")]
+    format: ExportFormat,
+    #[arg(short = 'O', long = "output", value_name = "FILE")]
+    output: Option<PathBuf>,
+    #[arg(short = 'a', long = "all", action = ArgAction::SetTrue)]
+    allow_any: bool,
+    #[arg(short = 'o', long = "offensive", action = ArgAction::SetTrue)]
+    offensive_only: bool,
+    #[arg(
+        long = "auto-index",
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "memory"
+    )]
+    auto_index: Option<AutoIndex>,
+    #[arg(long = "verbose", action = ArgAction::SetTrue)]
+    verbose: bool,
+    #[arg(value_name = "SOURCE")]
+    sources: Vec<String>,
+}
+
+fn main() {
+    let args = Args::parse();
+    init_logging(args.verbose, "warn,rustune=info,fortune_export=info");
+    if let Err(err) = run(args) {
+        eprintln!("fortune-export: {err:#}");
+        std::process::exit(exit_code_for(err.as_ref()));
+    }
+}
+
+#[instrument(skip_all)]
+fn run(args: Args) -> Result<()> {
+    let specs = parse_source_specs(&args.sources)?;
+    let config = DiscoveryConfig {
+        allow_any: args.allow_any,
+        offensive_only: args.offensive_only,
+        auto_index: args.auto_index.unwrap_or_default(),
+        ..DiscoveryConfig::default()
+    };
+    let discovered = discover_weighted_sources(&specs, &config)?;
+
+    let mut records = Vec::new();
+    for source in &discovered {
+        let db = open_export_file(&source.path, config.auto_index)?;
+        records.extend(export_records(&db)?);
+    }
+    debug!(
+        sources = discovered.len(),
+        records = records.len(),
+        "exporting records"
+    );
+
+    match &args.output {
+        Some(path) => {
+            let file = File::create(path).map_err(Error::io("creating", path))?;
+            let mut out = BufWriter::new(file);
+            write_records(&mut out, args.format, &records)
+                .and_then(|()| out.flush())
+                .map_err(Error::io("writing", path))?;
+        }
+        None => {
+            let mut out = io::stdout().lock();
+            write_records(&mut out, args.format, &records)?;
+        }
+    }
+    Ok(())
+}
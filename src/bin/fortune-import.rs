@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{ArgAction, Parser};
+use tracing::{debug, instrument};
+
+use rustune::datfile::dat_path_for_text;
+use rustune::error::{Error, exit_code_for};
+use rustune::import::{ImportFormat, parse_quotes, render_records};
+use rustune::logging::init_logging;
+use rustune::strfile_builder::{
+    BuildOptions, parse_delimiter, render_corpus, write_indexed_corpus,
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "fortune-import")]
+#[command(about = "Convert JSON, CSV, YAML or line-per-quote files into an indexed fortune corpus")]
+struct Args {
+    #[arg(long = "format", value_name = "FORMAT")]
+    format: Option<ImportFormat>,
+    #[arg(long = "field", value_name = "KEY")]
+    field: Option<String>,
+    #[arg(short = 'c', long = "delimiter", default_value = "%")]
+    delimiter: String,
+    #[arg(short = 's', long = "silent", action = ArgAction::SetTrue)]
+    silent: bool,
+    #[arg(long = "verbose", action = ArgAction::SetTrue)]
+    verbose: bool,
+    #[arg(value_name = "INPUT")]
+    input: PathBuf,
+    #[arg(value_name = "OUTPUT")]
+    output: PathBuf,
+}
+
+fn main() {
+    let args = Args::parse();
+    init_logging(args.verbose, "warn,rustune=info,fortune_import=info");
+    if let Err(err) = run(args) {
+        eprintln!("fortune-import: {err:#}");
+        std::process::exit(exit_code_for(err.as_ref()));
+    }
+}
+
+#[instrument(skip_all)]
+fn run(args: Args) -> Result<()> {
+    let delimiter = parse_delimiter(&args.delimiter)?;
+    let format = match args.format {
+        Some(format) => format,
+        None => ImportFormat::from_path(&args.input).ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "cannot infer format of '{}'; pass --format json|csv|yaml|lines",
+                args.input.display()
+            ))
+        })?,
+    };
+
+    let bytes = fs::read(&args.input).map_err(Error::io("reading", &args.input))?;
+    let quotes = parse_quotes(&args.input, &bytes, format, args.field.as_deref())?;
+    let records = render_records(&args.input, &quotes, delimiter)?;
+    let text = render_corpus(&records, delimiter);
+    let options = BuildOptions {
+        delimiter,
+        ..BuildOptions::default()
+    };
+    let stats = write_indexed_corpus(&args.output, &text, options)?;
+
+    debug!(
+        output = %args.output.display(),
+        record_count = stats.record_count,
+        "imported corpus"
+    );
+
+    if !args.silent {
+        println!(
+            "\"{}\" created from {}\n{} strings\nlongest string: {} bytes\nshortest string: {} bytes",
+            dat_path_for_text(&args.output).display(),
+            args.input.display(),
+            stats.record_count,
+            stats.longest_record,
+            stats.shortest_record
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::{ArgAction, Parser};
+use tracing::{debug, info, instrument};
+
+use rustune::datfile::{DatFile, dat_path_for_text};
+use rustune::error::{EXIT_FAILURE, Error, exit_code_for};
+use rustune::layout::expand_tabs;
+use rustune::lint::{Diagnostic, LintConfig, Severity, fix_corpus, lint_corpus, source_line};
+use rustune::logging::init_logging;
+use rustune::strfile_builder::{parse_delimiter, rewrite_indexed_corpus};
+
+#[derive(Debug, Parser)]
+#[command(name = "fortune-lint")]
+#[command(about = "Check fortune corpora for common formatting problems")]
+struct Args {
+    #[arg(short = 'c', long = "delimiter", default_value = "%")]
+    delimiter: String,
+    #[arg(long = "max-columns", default_value_t = 80)]
+    max_columns: usize,
+    #[arg(long = "max-length", default_value_t = 2000)]
+    max_length: usize,
+    #[arg(long = "fix", action = ArgAction::SetTrue)]
+    fix: bool,
+    #[arg(long = "verbose", action = ArgAction::SetTrue)]
+    verbose: bool,
+    #[arg(value_name = "FILE", required = true)]
+    files: Vec<PathBuf>,
+}
+
+fn main() {
+    let args = Args::parse();
+    init_logging(args.verbose, "warn,rustune=info,fortune_lint=info");
+    match run(args) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(EXIT_FAILURE),
+        Err(err) => {
+            eprintln!("fortune-lint: {err:#}");
+            std::process::exit(exit_code_for(err.as_ref()));
+        }
+    }
+}
+
+#[instrument(skip_all)]
+fn run(args: Args) -> Result<bool> {
+    let config = LintConfig {
+        delimiter: parse_delimiter(&args.delimiter)?,
+        max_columns: args.max_columns,
+        max_record_len: args.max_length,
+    };
+
+    let mut out = io::stderr().lock();
+    let (mut warnings, mut errors) = (0_usize, 0_usize);
+    for path in &args.files {
+        let mut input = fs::read(path).map_err(Error::io("reading", path))?;
+        if args.fix {
+            let fixed = fix_corpus(&input, &config);
+            if fixed != input {
+                apply_fix(path, &fixed)?;
+                input = fixed;
+            }
+        }
+
+        let diagnostics = lint_corpus(&input, &config);
+        debug!(path = %path.display(), diagnostics = diagnostics.len(), "linted file");
+        for diag in &diagnostics {
+            match diag.kind.severity() {
+                Severity::Warning => warnings += 1,
+                Severity::Error => errors += 1,
+            }
+            render_diagnostic(&mut out, path, &input, diag)?;
+        }
+    }
+
+    if warnings + errors > 0 {
+        writeln!(
+            out,
+            "fortune-lint: {warnings} warning(s) and {errors} error(s) in {} file(s)",
+            args.files.len()
+        )?;
+    }
+    Ok(warnings + errors == 0)
+}
+
+fn apply_fix(path: &Path, fixed: &[u8]) -> Result<()> {
+    let dat_path = dat_path_for_text(path);
+    if dat_path.exists() {
+        let previous = DatFile::read_from_path(&dat_path)?;
+        rewrite_indexed_corpus(path, fixed, &previous.header)?;
+    } else {
+        fs::write(path, fixed).map_err(Error::io("writing", path))?;
+    }
+    info!(path = %path.display(), "applied safe fixes");
+    Ok(())
+}
+
+fn render_diagnostic(
+    out: &mut dyn Write,
+    path: &Path,
+    input: &[u8],
+    diag: &Diagnostic,
+) -> io::Result<()> {
+    let line_no = diag.line.to_string();
+    let gutter = " ".repeat(line_no.len());
+    writeln!(
+        out,
+        "{}[{}]: {}",
+        diag.kind.severity(),
+        diag.kind.code(),
+        diag.message
+    )?;
+    writeln!(
+        out,
+        "{gutter}--> {}:{}:{}",
+        path.display(),
+        diag.line,
+        diag.column
+    )?;
+    writeln!(out, "{gutter} |")?;
+    writeln!(
+        out,
+        "{line_no} | {}",
+        expand_tabs(&source_line(input, diag.line))
+    )?;
+    writeln!(
+        out,
+        "{gutter} | {}{}",
+        " ".repeat(diag.column.saturating_sub(1)),
+        "^".repeat(diag.width.max(1))
+    )?;
+    if diag.kind.fixable() {
+        writeln!(out, "{gutter} = help: run with --fix to apply the safe fix")?;
+    }
+    writeln!(out)
+}
@@ -8,6 +8,8 @@ use clap::Parser;
 use serde::Serialize;
 use tracing::{debug, instrument, warn};
 
+use rustune::datfile::is_sidecar_file;
+use rustune::error::exit_code_for;
 use rustune::logging::init_logging;
 
 #[derive(Debug, Parser)]
@@ -71,7 +73,7 @@ fn main() {
     init_logging(args.verbose, "warn,rustune=info,fortune_parity=info");
     if let Err(err) = run(args) {
         eprintln!("fortune-parity: {err:#}");
-        std::process::exit(1);
+        std::process::exit(exit_code_for(err.as_ref()));
     }
 }
 
@@ -142,7 +144,7 @@ fn ensure_corpus_dat_files(corpus_dir: &Path, strfile_bin: &Path) -> Result<()>
         let Some(name) = path.file_name().and_then(|s| s.to_str()) else {
             continue;
         };
-        if name.ends_with(".dat") || name.ends_with(".u8") {
+        if is_sidecar_file(name) {
             continue;
         }
         let dat = PathBuf::from(format!("{}.dat", path.display()));
@@ -1,13 +1,15 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result, bail};
+use anyhow::Result;
 use clap::{ArgAction, Parser};
 use tracing::{debug, instrument};
 
-use rustune::datfile::dat_path_for_text;
+use rustune::datfile::{FortuneFile, dat_path_for_text};
+use rustune::error::{Error, exit_code_for};
 use rustune::logging::init_logging;
-use rustune::strfile_builder::{BuildOptions, build_dat_from_text};
+use rustune::strfile_builder::{BuildOptions, build_dat_from_text, parse_delimiter};
+use rustune::tag_index::TagIndex;
 
 #[derive(Debug, Parser)]
 #[command(name = "strfile")]
@@ -23,6 +25,8 @@ struct Args {
     silent: bool,
     #[arg(long = "allow-empty", action = ArgAction::SetTrue)]
     allow_empty: bool,
+    #[arg(long = "no-tags", action = ArgAction::SetTrue)]
+    no_tags: bool,
     #[arg(long = "verbose", action = ArgAction::SetTrue)]
     verbose: bool,
     #[arg(value_name = "INPUT")]
@@ -36,7 +40,7 @@ fn main() {
     init_logging(args.verbose, "warn,rustune=info,strfile=info");
     if let Err(err) = run(args) {
         eprintln!("strfile: {err:#}");
-        std::process::exit(1);
+        std::process::exit(exit_code_for(err.as_ref()));
     }
 }
 
@@ -48,8 +52,7 @@ fn run(args: Args) -> Result<()> {
         .clone()
         .unwrap_or_else(|| dat_path_for_text(&args.input));
 
-    let input_bytes = fs::read(&args.input)
-        .with_context(|| format!("failed to read {}", args.input.display()))?;
+    let input_bytes = fs::read(&args.input).map_err(Error::io("reading", &args.input))?;
     let options = BuildOptions {
         delimiter,
         randomize_offsets: args.randomize_offsets,
@@ -58,6 +61,12 @@ fn run(args: Args) -> Result<()> {
     };
     let (dat, stats) = build_dat_from_text(&input_bytes, options)?;
     dat.write_to_path(&output)?;
+    let tag_count = if args.no_tags {
+        0
+    } else {
+        let db = FortuneFile::from_parts(&args.input, Some(output.clone()), dat, input_bytes)?;
+        write_tag_index(&db, &tag_path_for_output(&output))?
+    };
 
     debug!(
         output = %output.display(),
@@ -73,14 +82,29 @@ fn run(args: Args) -> Result<()> {
             stats.longest_record,
             stats.shortest_record
         );
+        if tag_count > 0 {
+            println!("{tag_count} tags indexed");
+        }
     }
     Ok(())
 }
 
-fn parse_delimiter(value: &str) -> Result<u8> {
-    let bytes = value.as_bytes();
-    if bytes.len() != 1 {
-        bail!("delimiter must be a single byte, got '{value}'");
+fn write_tag_index(db: &FortuneFile, path: &Path) -> Result<usize> {
+    let index = TagIndex::build(db)?;
+    if index.is_empty() {
+        if path.exists() {
+            fs::remove_file(path).map_err(Error::io("removing stale tag index", path))?;
+        }
+        return Ok(0);
+    }
+    index.write_to_path(path)?;
+    debug!(path = %path.display(), tags = index.tags.len(), "wrote tag index");
+    Ok(index.tags.len())
+}
+
+fn tag_path_for_output(output: &Path) -> PathBuf {
+    match output.to_str().and_then(|path| path.strip_suffix(".dat")) {
+        Some(stem) => PathBuf::from(format!("{stem}.tags")),
+        None => PathBuf::from(format!("{}.tags", output.display())),
     }
-    Ok(bytes[0])
 }
@@ -0,0 +1,73 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::datfile::{DatFile, FortuneFile, LengthFilter};
+use crate::error::Result;
+use crate::fortune_source::{FortuneSource, SourceKind, SourceMetadata};
+
+pub const BUILTIN_SOURCE_NAME: &str = "builtin";
+// The path discovery records for the embedded corpus; the colon keeps it
+// from ever matching a real file or directory named `builtin`.
+pub const BUILTIN_SOURCE_PATH: &str = "builtin:";
+
+const TEXT: &[u8] = include_bytes!("../corpus/builtin");
+const DAT: &[u8] = include_bytes!("../corpus/builtin.dat");
+
+#[derive(Debug, Clone)]
+pub struct BuiltinSource {
+    db: FortuneFile,
+}
+
+impl BuiltinSource {
+    pub fn load() -> Result<Self> {
+        let dat = DatFile::read_from_bytes(DAT)?;
+        let db = FortuneFile::from_parts(Path::new(BUILTIN_SOURCE_NAME), None, dat, TEXT.to_vec())?;
+        Ok(Self { db })
+    }
+
+    pub fn into_file(self) -> FortuneFile {
+        self.db
+    }
+}
+
+impl FortuneSource for BuiltinSource {
+    fn display_name(&self) -> String {
+        BUILTIN_SOURCE_NAME.to_string()
+    }
+
+    fn record_count(&self) -> usize {
+        self.db.record_count()
+    }
+
+    fn record_bytes(&self, index: usize) -> Result<Cow<'_, [u8]>> {
+        self.db.record_bytes(index).map(Cow::Borrowed)
+    }
+
+    fn metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            kind: SourceKind::Builtin,
+            path: None,
+            offensive: false,
+        }
+    }
+
+    fn candidate_indices(&self, filter: LengthFilter) -> Result<Vec<usize>> {
+        self.db.candidate_indices(filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strfile_builder::{BuildOptions, build_dat_from_text};
+
+    #[test]
+    fn embedded_dat_matches_embedded_text() {
+        let (dat, _) = build_dat_from_text(TEXT, BuildOptions::default()).expect("build dat");
+        assert_eq!(dat.to_bytes().expect("encode dat"), DAT);
+
+        let source = BuiltinSource::load().expect("load builtin");
+        assert_eq!(source.record_count(), dat.offsets.len());
+        assert!(source.metadata().path.is_none());
+    }
+}
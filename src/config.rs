@@ -0,0 +1,155 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tracing::{debug, instrument};
+
+use crate::discovery::Overlap;
+use crate::error::{Error, Result};
+use crate::fortune_engine::{RecordSelection, Weighting};
+use crate::schedule::ScheduleRule;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub wait: WaitConfig,
+    pub selection: SelectionConfig,
+    pub schedule: Vec<ScheduleRule>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WaitConfig {
+    pub wpm: Option<u32>,
+    pub min_seconds: Option<f64>,
+    pub max_seconds: Option<f64>,
+    pub countdown: Option<bool>,
+    pub clear: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SelectionConfig {
+    pub records: Option<RecordSelection>,
+    pub weighting: Option<Weighting>,
+    pub overlap: Option<Overlap>,
+}
+
+impl Config {
+    pub fn parse(path: &Path, text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text).map_err(|err| Error::InvalidConfig {
+            path: path.to_path_buf(),
+            reason: err.message().to_string(),
+        })?;
+        config
+            .wait
+            .validate()
+            .map_err(|reason| Error::InvalidConfig {
+                path: path.to_path_buf(),
+                reason: format!("[wait]: {reason}"),
+            })?;
+        for rule in &config.schedule {
+            rule.validate().map_err(|err| Error::InvalidConfig {
+                path: path.to_path_buf(),
+                reason: format!("schedule for '{}': {err}", rule.source.display()),
+            })?;
+        }
+        Ok(config)
+    }
+
+    // An explicit path must exist; the default location is optional.
+    #[instrument(skip_all)]
+    pub fn load(explicit: Option<&Path>) -> Result<Self> {
+        let (path, required) = match explicit {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                debug!(path = %path.display(), "loaded config");
+                Self::parse(&path, &text)
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !required => Ok(Self::default()),
+            Err(err) => Err(Error::io("reading config", &path)(err)),
+        }
+    }
+}
+
+impl WaitConfig {
+    fn validate(&self) -> std::result::Result<(), String> {
+        if self.wpm == Some(0) {
+            return Err("wpm must be at least 1".into());
+        }
+        for (key, value) in [
+            ("min_seconds", self.min_seconds),
+            ("max_seconds", self.max_seconds),
+        ] {
+            if let Some(value) = value
+                && !(value.is_finite() && value >= 0.0)
+            {
+                return Err(format!("{key} must be a non-negative number, got {value}"));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_seconds, self.max_seconds)
+            && max < min
+        {
+            return Err(format!(
+                "max_seconds ({max}) is less than min_seconds ({min})"
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|v| !v.is_empty())
+                .map(|home| PathBuf::from(home).join(".config"))
+        })?;
+    Some(base.join("rustune").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_wait_section_and_rejects_unknown_keys() {
+        let path = Path::new("config.toml");
+        let config = Config::parse(path, "[wait]\nwpm = 250\nmax_seconds = 12\n").expect("parse");
+        assert_eq!(config.wait.wpm, Some(250));
+        assert_eq!(config.wait.max_seconds, Some(12.0));
+        assert_eq!(config.wait.countdown, None);
+        assert_eq!(config.selection.records, None);
+
+        let config = Config::parse(
+            path,
+            "[selection]\nrecords = \"fair\"\nweighting = \"sqrt\"\n",
+        )
+        .expect("parse");
+        assert_eq!(config.selection.records, Some(RecordSelection::Fair));
+        assert_eq!(config.selection.weighting, Some(Weighting::Sqrt));
+
+        let err = Config::parse(path, "[wait]\nwmp = 250\n").expect_err("typo");
+        assert!(matches!(err, Error::InvalidConfig { .. }), "{err:?}");
+
+        for wait in [
+            "wpm = 0",
+            "min_seconds = -1",
+            "max_seconds = nan",
+            "min_seconds = 10\nmax_seconds = 5",
+        ] {
+            let err = Config::parse(path, &format!("[wait]\n{wait}\n")).expect_err(wait);
+            assert!(err.to_string().contains("[wait]"), "{wait}: {err}");
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use regex::Regex;
+use tracing::{debug, instrument};
+
+use crate::auto_index::{AutoIndex, open_fortune_file};
+use crate::dedupe::normalize_record;
+use crate::error::{Error, Result};
+use crate::strfile_builder::{BuildOptions, BuildStats, render_corpus, write_indexed_corpus};
+
+// Regex splits put records no pattern matched in this bucket.
+pub const REST_BUCKET: &str = "rest";
+
+#[derive(Debug, Clone)]
+pub struct CorpusRecords {
+    pub delimiter: u8,
+    pub records: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SplitRule {
+    Regex(Vec<(String, Regex)>),
+    Length(Vec<usize>),
+    Count(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bucket {
+    pub name: String,
+    pub records: Vec<Vec<u8>>,
+}
+
+#[instrument(skip_all, fields(path = %path.display()))]
+pub fn read_records(path: &Path) -> Result<CorpusRecords> {
+    let db = open_fortune_file(path, AutoIndex::Memory)?;
+    let mut order: Vec<(u32, usize)> = db
+        .dat
+        .offsets
+        .iter()
+        .enumerate()
+        .map(|(idx, offset)| (*offset, idx))
+        .collect();
+    order.sort_unstable();
+    let mut records = Vec::with_capacity(order.len());
+    for (_, idx) in order {
+        records.push(db.record_bytes(idx)?.to_vec());
+    }
+    Ok(CorpusRecords {
+        delimiter: db.dat.header.delim,
+        records,
+    })
+}
+
+pub fn merge_records(inputs: Vec<CorpusRecords>, drop_duplicates: bool) -> (Vec<Vec<u8>>, usize) {
+    let mut seen = HashSet::new();
+    let mut merged = Vec::new();
+    let mut dropped = 0;
+    for record in inputs.into_iter().flat_map(|input| input.records) {
+        if drop_duplicates && !seen.insert(normalize_record(&String::from_utf8_lossy(&record))) {
+            dropped += 1;
+            continue;
+        }
+        merged.push(record);
+    }
+    debug!(merged = merged.len(), dropped, "merged corpora");
+    (merged, dropped)
+}
+
+pub fn split_records(records: Vec<Vec<u8>>, rule: &SplitRule) -> Vec<Bucket> {
+    let mut buckets: Vec<Bucket> = match rule {
+        SplitRule::Regex(patterns) => patterns
+            .iter()
+            .map(|(name, _)| name.clone())
+            .chain([REST_BUCKET.to_string()])
+            .map(empty_bucket)
+            .collect(),
+        SplitRule::Length(limits) => {
+            let mut names: Vec<String> = limits.iter().map(|limit| format!("le{limit}")).collect();
+            names.push(format!("gt{}", limits.last().copied().unwrap_or(0)));
+            names.into_iter().map(empty_bucket).collect()
+        }
+        SplitRule::Count(_) => Vec::new(),
+    };
+
+    for (idx, record) in records.into_iter().enumerate() {
+        let slot = match rule {
+            SplitRule::Regex(patterns) => {
+                let text = String::from_utf8_lossy(&record);
+                patterns
+                    .iter()
+                    .position(|(_, regex)| regex.is_match(&text))
+                    .unwrap_or(patterns.len())
+            }
+            SplitRule::Length(limits) => limits
+                .iter()
+                .position(|limit| record.len() <= *limit)
+                .unwrap_or(limits.len()),
+            SplitRule::Count(per_file) => {
+                let slot = idx / per_file;
+                if slot == buckets.len() {
+                    buckets.push(empty_bucket(format!("{:03}", slot + 1)));
+                }
+                slot
+            }
+        };
+        buckets[slot].records.push(record);
+    }
+    buckets.retain(|bucket| !bucket.records.is_empty());
+    buckets
+}
+
+pub fn write_corpus(path: &Path, records: &[Vec<u8>], delimiter: u8) -> Result<BuildStats> {
+    for (idx, record) in records.iter().enumerate() {
+        let delimiter_line = record
+            .split(|b| *b == b'\n')
+            .any(|line| line.strip_suffix(b"\r").unwrap_or(line) == [delimiter]);
+        if delimiter_line {
+            return Err(Error::InvalidArgument(format!(
+                "record {} for {} contains a line consisting only of the delimiter '{}'",
+                idx + 1,
+                path.display(),
+                delimiter as char
+            )));
+        }
+    }
+    let options = BuildOptions {
+        delimiter,
+        ..BuildOptions::default()
+    };
+    write_indexed_corpus(path, &render_corpus(records, delimiter), options)
+}
+
+fn empty_bucket(name: String) -> Bucket {
+    Bucket {
+        name,
+        records: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(items: &[&str]) -> Vec<Vec<u8>> {
+        items.iter().map(|item| item.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn merge_drops_normalized_duplicates_and_split_buckets_records() {
+        let inputs = vec![
+            CorpusRecords {
+                delimiter: b'%',
+                records: records(&["Rust is fun.\n", "short\n"]),
+            },
+            CorpusRecords {
+                delimiter: b'#',
+                records: records(&["rust IS fun\n", "A much longer fortune here.\n"]),
+            },
+        ];
+        let (merged, dropped) = merge_records(inputs, true);
+        assert_eq!(dropped, 1);
+        assert_eq!(merged.len(), 3);
+
+        let rule = SplitRule::Regex(vec![(
+            "rust".into(),
+            Regex::new("(?i)rust").expect("regex"),
+        )]);
+        let names: Vec<(String, usize)> = split_records(merged.clone(), &rule)
+            .into_iter()
+            .map(|bucket| (bucket.name, bucket.records.len()))
+            .collect();
+        assert_eq!(names, vec![("rust".into(), 1), ("rest".into(), 2)]);
+
+        let by_length = split_records(merged.clone(), &SplitRule::Length(vec![10]));
+        assert_eq!(by_length[0].name, "le10");
+        assert_eq!(by_length[0].records, records(&["short\n"]));
+        assert_eq!(by_length[1].name, "gt10");
+
+        let by_count = split_records(merged, &SplitRule::Count(2));
+        assert_eq!(by_count.len(), 2);
+        assert_eq!(by_count[1].name, "002");
+    }
+}
@@ -2,13 +2,15 @@ use std::fmt::{Display, Formatter};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result, bail};
 use tracing::{debug, instrument};
 
+use crate::error::{Error, Result};
+
 pub const STRFILE_VERSION: u32 = 2;
 pub const STR_RANDOM: u32 = 0x1;
 pub const STR_ORDERED: u32 = 0x2;
 pub const STR_ROTATED: u32 = 0x4;
+pub const STR_COMMENTS: u32 = 0x8;
 pub const HEADER_BYTES: usize = 24;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -30,7 +32,9 @@ pub struct DatFile {
 #[derive(Debug, Clone)]
 pub struct FortuneFile {
     pub text_path: PathBuf,
-    pub dat_path: PathBuf,
+    // The on-disk index this file was opened with; `None` when the index
+    // only exists in memory.
+    pub dat_path: Option<PathBuf>,
     pub dat: DatFile,
     pub bytes: Vec<u8>,
 }
@@ -41,11 +45,16 @@ pub struct RecordSpan {
     pub end: usize,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum LengthFilter {
+    #[default]
     Any,
-    Short { threshold: usize },
-    Long { threshold: usize },
+    Short {
+        threshold: usize,
+    },
+    Long {
+        threshold: usize,
+    },
 }
 
 impl LengthFilter {
@@ -72,15 +81,17 @@ impl std::error::Error for DatValidationError {}
 impl DatFile {
     #[instrument(skip_all, fields(path = %path.display()))]
     pub fn read_from_path(path: &Path) -> Result<Self> {
-        let bytes = fs::read(path)
-            .with_context(|| format!("failed reading dat file {}", path.display()))?;
-        Self::read_from_bytes(&bytes)
+        let bytes = fs::read(path).map_err(Error::io("reading dat file", path))?;
+        Self::read_from_bytes(&bytes).map_err(|source| Error::CorruptDat {
+            path: path.to_path_buf(),
+            source,
+        })
     }
 
     #[instrument(skip_all)]
-    pub fn read_from_bytes(bytes: &[u8]) -> Result<Self> {
+    pub fn read_from_bytes(bytes: &[u8]) -> Result<Self, DatValidationError> {
         if bytes.len() < HEADER_BYTES {
-            bail!(DatValidationError("dat file shorter than header"));
+            return Err(DatValidationError("dat file shorter than header"));
         }
 
         let version = be_u32(&bytes[0..4]);
@@ -92,12 +103,12 @@ impl DatFile {
 
         let expected_offsets_bytes = (numstr as usize)
             .checked_mul(4)
-            .ok_or_else(|| DatValidationError("offset table size overflow"))?;
+            .ok_or(DatValidationError("offset table size overflow"))?;
         let expected_total = HEADER_BYTES
             .checked_add(expected_offsets_bytes)
-            .ok_or_else(|| DatValidationError("dat size overflow"))?;
+            .ok_or(DatValidationError("dat size overflow"))?;
         if bytes.len() < expected_total {
-            bail!(DatValidationError("dat file missing offset entries"));
+            return Err(DatValidationError("dat file missing offset entries"));
         }
 
         let mut offsets = Vec::with_capacity(numstr as usize);
@@ -121,17 +132,17 @@ impl DatFile {
     #[instrument(skip_all, fields(path = %path.display()))]
     pub fn write_to_path(&self, path: &Path) -> Result<()> {
         let encoded = self.to_bytes()?;
-        fs::write(path, encoded).with_context(|| format!("failed writing {}", path.display()))
+        fs::write(path, encoded).map_err(Error::io("writing", path))
     }
 
     #[instrument(skip_all)]
-    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, DatValidationError> {
         if self.offsets.len() > (u32::MAX as usize) {
-            bail!(DatValidationError("too many offsets for STRFILE u32"));
+            return Err(DatValidationError("too many offsets for STRFILE u32"));
         }
         if self.header.numstr != self.offsets.len() as u32 {
-            bail!(DatValidationError(
-                "header numstr does not match offset count"
+            return Err(DatValidationError(
+                "header numstr does not match offset count",
             ));
         }
 
@@ -155,8 +166,16 @@ impl FortuneFile {
     pub fn open(text_path: &Path) -> Result<Self> {
         let dat_path = dat_path_for_text(text_path);
         let dat = DatFile::read_from_path(&dat_path)?;
-        let bytes = fs::read(text_path)
-            .with_context(|| format!("failed reading fortune text {}", text_path.display()))?;
+        let bytes = fs::read(text_path).map_err(Error::io("reading fortune text", text_path))?;
+        Self::from_parts(text_path, Some(dat_path), dat, bytes)
+    }
+
+    pub fn from_parts(
+        text_path: &Path,
+        dat_path: Option<PathBuf>,
+        dat: DatFile,
+        bytes: Vec<u8>,
+    ) -> Result<Self> {
         let db = Self {
             text_path: text_path.to_path_buf(),
             dat_path,
@@ -177,12 +196,11 @@ impl FortuneFile {
         let file_len = self.bytes.len() as u64;
         for offset in &self.dat.offsets {
             if (*offset as u64) > file_len {
-                bail!(
-                    "offset {} is out of range for file {} bytes in {}",
-                    offset,
+                return Err(Error::OffsetOutOfRange {
+                    path: self.text_path.clone(),
+                    offset: *offset,
                     file_len,
-                    self.text_path.display()
-                );
+                });
             }
         }
         Ok(())
@@ -197,14 +215,14 @@ impl FortuneFile {
             .dat
             .offsets
             .get(index)
-            .with_context(|| format!("record index {index} out of range"))?
-            as usize;
+            .ok_or(Error::RecordOutOfRange { index })? as usize;
         let end = self.find_delimiter_start(start).unwrap_or(self.bytes.len());
         if start > end || end > self.bytes.len() {
-            bail!(
-                "invalid record span [{start}, {end}) for file size {}",
-                self.bytes.len()
-            );
+            return Err(Error::InvalidSpan {
+                start,
+                end,
+                file_len: self.bytes.len(),
+            });
         }
         Ok(RecordSpan { start, end })
     }
@@ -287,6 +305,14 @@ pub fn dat_path_for_text(path: &Path) -> PathBuf {
     PathBuf::from(format!("{}.dat", path.display()))
 }
 
+// Files written next to a corpus that are never a corpus themselves: the
+// strfile index, the `.tags` sidecar and fortune-mod's `.u8` recode copy.
+pub fn is_sidecar_file(name: &str) -> bool {
+    [".dat", ".tags", ".u8"]
+        .iter()
+        .any(|extension| name.ends_with(extension))
+}
+
 fn be_u32(bytes: &[u8]) -> u32 {
     u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
 }
@@ -0,0 +1,385 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, instrument, warn};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::datfile::FortuneFile;
+use crate::error::Result;
+use crate::fortune_engine::LoadedSource;
+use crate::hash::{FNV_OFFSET, fnv1a};
+use crate::strfile_builder::{render_corpus, rewrite_indexed_corpus};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DedupeConfig {
+    pub threshold: f64,
+    pub shingle_chars: usize,
+    pub hashes: usize,
+    pub bands: usize,
+    pub fuzzy: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RecordRef {
+    pub source: usize,
+    pub record: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    Exact,
+    Fuzzy,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Duplicate {
+    pub record: RecordRef,
+    pub kind: MatchKind,
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    pub keep: RecordRef,
+    pub duplicates: Vec<Duplicate>,
+}
+
+struct Fingerprint {
+    record: RecordRef,
+    normalized: String,
+    shingles: HashSet<u64>,
+    signature: Vec<u64>,
+}
+
+impl Default for DedupeConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 0.8,
+            shingle_chars: 5,
+            hashes: 64,
+            bands: 16,
+            fuzzy: true,
+        }
+    }
+}
+
+pub fn normalize_record(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut pending_space = false;
+    for ch in text.nfkc().flat_map(char::to_lowercase) {
+        if ch.is_alphanumeric() {
+            if pending_space && !out.is_empty() {
+                out.push(' ');
+            }
+            pending_space = false;
+            out.push(ch);
+        } else if ch.is_whitespace() {
+            pending_space = true;
+        }
+    }
+    out
+}
+
+#[instrument(skip_all, fields(sources = sources.len()))]
+pub fn find_duplicates(
+    sources: &[LoadedSource],
+    config: &DedupeConfig,
+) -> Result<Vec<DuplicateGroup>> {
+    let mut fingerprints = Vec::new();
+    for (source_idx, loaded) in sources.iter().enumerate() {
+        for record_idx in 0..loaded.source.record_count() {
+            let text = loaded.source.record_text_lossy(record_idx)?;
+            let normalized = normalize_record(&text);
+            if normalized.is_empty() {
+                continue;
+            }
+            let shingles = shingles(&normalized, config.shingle_chars);
+            let signature = if config.fuzzy {
+                minhash(&shingles, config.hashes)
+            } else {
+                Vec::new()
+            };
+            fingerprints.push(Fingerprint {
+                record: RecordRef {
+                    source: source_idx,
+                    record: record_idx,
+                },
+                normalized,
+                shingles,
+                signature,
+            });
+        }
+    }
+
+    let mut parent: Vec<usize> = (0..fingerprints.len()).collect();
+    let mut matched: HashMap<usize, (MatchKind, f64)> = HashMap::new();
+
+    let mut exact: HashMap<&str, usize> = HashMap::new();
+    for (idx, fp) in fingerprints.iter().enumerate() {
+        match exact.get(fp.normalized.as_str()) {
+            Some(&first) => {
+                union(&mut parent, first, idx);
+                matched.insert(idx, (MatchKind::Exact, 1.0));
+            }
+            None => {
+                exact.insert(&fp.normalized, idx);
+            }
+        }
+    }
+
+    if config.fuzzy {
+        let rows = (config.hashes / config.bands.max(1)).max(1);
+        let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+        for (idx, fp) in fingerprints.iter().enumerate() {
+            if matched.contains_key(&idx) {
+                continue;
+            }
+            for (band, chunk) in fp.signature.chunks(rows).enumerate() {
+                let key = chunk.iter().fold(FNV_OFFSET, |acc, h| mix(acc ^ h));
+                buckets.entry((band, key)).or_default().push(idx);
+            }
+        }
+        let mut checked = HashSet::new();
+        for members in buckets.values() {
+            for (pos, &a) in members.iter().enumerate() {
+                for &b in &members[pos + 1..] {
+                    if !checked.insert((a, b)) || find(&mut parent, a) == find(&mut parent, b) {
+                        continue;
+                    }
+                    let similarity = jaccard(&fingerprints[a].shingles, &fingerprints[b].shingles);
+                    if similarity >= config.threshold {
+                        union(&mut parent, a, b);
+                        let later = a.max(b);
+                        matched
+                            .entry(later)
+                            .or_insert((MatchKind::Fuzzy, similarity));
+                    }
+                }
+            }
+        }
+        debug!(
+            buckets = buckets.len(),
+            pairs = checked.len(),
+            "compared minhash candidates"
+        );
+    }
+
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for idx in 0..fingerprints.len() {
+        let root = find(&mut parent, idx);
+        groups.entry(root).or_default().push(idx);
+    }
+    let out: Vec<DuplicateGroup> = groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            let keep = members[0];
+            let duplicates = members[1..]
+                .iter()
+                .map(|&idx| {
+                    let (kind, similarity) = match matched.get(&idx) {
+                        Some(&(MatchKind::Exact, _)) => (MatchKind::Exact, 1.0),
+                        _ => (
+                            MatchKind::Fuzzy,
+                            jaccard(&fingerprints[keep].shingles, &fingerprints[idx].shingles),
+                        ),
+                    };
+                    Duplicate {
+                        record: fingerprints[idx].record,
+                        kind,
+                        similarity,
+                    }
+                })
+                .collect();
+            DuplicateGroup {
+                keep: fingerprints[keep].record,
+                duplicates,
+            }
+        })
+        .collect();
+    debug!(
+        records = fingerprints.len(),
+        groups = out.len(),
+        "found duplicate groups"
+    );
+    Ok(out)
+}
+
+#[instrument(skip_all, fields(path = %path.display(), removed = remove.len()))]
+pub fn rewrite_without(path: &Path, remove: &BTreeSet<usize>) -> Result<usize> {
+    let db = FortuneFile::open(path)?;
+    let mut kept: Vec<(u32, usize)> = db
+        .dat
+        .offsets
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| !remove.contains(idx))
+        .map(|(idx, offset)| (*offset, idx))
+        .collect();
+    kept.sort_unstable();
+    let mut records = Vec::with_capacity(kept.len());
+    for (_, idx) in &kept {
+        records.push(db.record_bytes(*idx)?);
+    }
+
+    let text = render_corpus(&records, db.dat.header.delim);
+    rewrite_indexed_corpus(path, &text, &db.dat.header)?;
+    Ok(kept.len())
+}
+
+pub fn removals_by_path(
+    sources: &[LoadedSource],
+    groups: &[DuplicateGroup],
+) -> BTreeMap<PathBuf, BTreeSet<usize>> {
+    let mut out: BTreeMap<PathBuf, BTreeSet<usize>> = BTreeMap::new();
+    for dup in groups.iter().flat_map(|group| &group.duplicates) {
+        let source = &sources[dup.record.source].source;
+        match source.metadata().path {
+            Some(path) => {
+                out.entry(path).or_default().insert(dup.record.record);
+            }
+            None => {
+                warn!(source = %source.display_name(), "cannot rewrite a source without a file")
+            }
+        }
+    }
+    out
+}
+
+fn shingles(normalized: &str, width: usize) -> HashSet<u64> {
+    let chars: Vec<char> = normalized.chars().collect();
+    if chars.len() <= width {
+        return HashSet::from([fnv1a(normalized.as_bytes())]);
+    }
+    chars
+        .windows(width)
+        .map(|window| {
+            let shingle: String = window.iter().collect();
+            fnv1a(shingle.as_bytes())
+        })
+        .collect()
+}
+
+fn minhash(shingles: &HashSet<u64>, hashes: usize) -> Vec<u64> {
+    (0..hashes as u64)
+        .map(|seed| {
+            shingles
+                .iter()
+                .map(|h| mix(h ^ seed.wrapping_mul(0x9e37_79b9_7f4a_7c15)))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let shared = a.intersection(b).count();
+    let total = a.len() + b.len() - shared;
+    if total == 0 {
+        1.0
+    } else {
+        shared as f64 / total as f64
+    }
+}
+
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn find(parent: &mut [usize], mut idx: usize) -> usize {
+    while parent[idx] != idx {
+        parent[idx] = parent[parent[idx]];
+        idx = parent[idx];
+    }
+    idx
+}
+
+fn union(parent: &mut [usize], a: usize, b: usize) {
+    let (ra, rb) = (find(parent, a), find(parent, b));
+    if ra != rb {
+        parent[ra.max(rb)] = ra.min(rb);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::datfile::{LengthFilter, STR_COMMENTS, STR_ROTATED, dat_path_for_text};
+    use crate::fortune_source::MemorySource;
+    use crate::strfile_builder::{BuildOptions, build_dat_from_text};
+
+    fn loaded(name: &str, records: &[&str]) -> LoadedSource {
+        let source = MemorySource::new(name, records.iter().copied());
+        LoadedSource::new(Arc::new(source), None, LengthFilter::Any).expect("load")
+    }
+
+    #[test]
+    fn groups_exact_and_fuzzy_duplicates_across_sources() {
+        assert_eq!(normalize_record("  Ｈｅｌｌｏ,\tWORLD!! "), "hello world");
+
+        let sources = [
+            loaded(
+                "a",
+                &[
+                    "The quick brown fox jumps over the lazy dog while the farmer sleeps in the sun.",
+                    "Unrelated wisdom.",
+                ],
+            ),
+            loaded(
+                "b",
+                &[
+                    "the quick  brown fox -- jumps over the lazy dog while the farmer sleeps in the sun",
+                    "The quick brown fox jumps over the lazy dog while the farmer sleeps in the sunshine!",
+                ],
+            ),
+        ];
+        let groups = find_duplicates(&sources, &DedupeConfig::default()).expect("dedupe");
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0].keep,
+            RecordRef {
+                source: 0,
+                record: 0
+            }
+        );
+        let kinds: Vec<_> = groups[0].duplicates.iter().map(|dup| dup.kind).collect();
+        assert_eq!(kinds, vec![MatchKind::Exact, MatchKind::Fuzzy]);
+        assert!(groups[0].duplicates[1].similarity >= 0.8);
+
+        let exact_only = DedupeConfig {
+            fuzzy: false,
+            ..DedupeConfig::default()
+        };
+        let groups = find_duplicates(&sources, &exact_only).expect("dedupe");
+        assert_eq!(groups[0].duplicates.len(), 1);
+    }
+
+    #[test]
+    fn rewrite_keeps_rotation_and_comment_flags() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("rot");
+        let text = b"Uryyb\n%\nUryyb\n%\nJbeyq\n";
+        let (mut dat, _) = build_dat_from_text(text, BuildOptions::default()).expect("build dat");
+        dat.header.flags |= STR_ROTATED | STR_COMMENTS;
+        fs::write(&path, text).expect("write text");
+        dat.write_to_path(&dat_path_for_text(&path))
+            .expect("write dat");
+
+        assert_eq!(
+            rewrite_without(&path, &BTreeSet::from([1])).expect("rewrite"),
+            2
+        );
+        let db = FortuneFile::open(&path).expect("open");
+        assert_eq!(db.bytes, b"Uryyb\n%\nJbeyq\n");
+        assert_eq!(
+            db.dat.header.flags & (STR_ROTATED | STR_COMMENTS),
+            STR_ROTATED | STR_COMMENTS
+        );
+    }
+}
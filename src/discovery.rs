@@ -1,29 +1,95 @@
-use std::collections::BTreeSet;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use anyhow::{Context, Result, bail};
+use serde::Deserialize;
 use tracing::{debug, instrument, trace, warn};
 
-use crate::datfile::dat_path_for_text;
+use crate::auto_index::{AutoIndex, is_indexable, is_indexable_in_dir};
+use crate::datfile::is_sidecar_file;
+use crate::error::{Error, Result};
+use crate::schedule::Schedule;
 use crate::sources::{SourceSpec, WeightedSource};
 
 const DEFAULT_FORTUNE_PATH: &str = "/usr/share/fortune:/usr/local/share/fortune:/usr/share/games/fortunes:/usr/local/share/games/fortunes";
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct DiscoveryConfig {
     pub allow_any: bool,
     pub offensive_only: bool,
+    pub auto_index: AutoIndex,
+    pub overlap: Overlap,
+    pub schedule: Option<Schedule>,
 }
 
-impl Default for DiscoveryConfig {
-    fn default() -> Self {
-        Self {
-            allow_any: false,
-            offensive_only: false,
+// How a file reached through several specs (`fortunes fortunes/linux`,
+// `all` plus a path, the same file twice) is weighted once merged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Overlap {
+    #[default]
+    Explicit,
+    Sum,
+}
+
+impl FromStr for Overlap {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "explicit" => Ok(Self::Explicit),
+            "sum" => Ok(Self::Sum),
+            other => Err(Error::InvalidArgument(format!(
+                "unknown overlap policy '{other}' (expected explicit|sum)"
+            ))),
+        }
+    }
+}
+
+impl Overlap {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Explicit => "explicit",
+            Self::Sum => "sum",
         }
     }
+
+    fn merge(self, kept: &mut Discovered, next: Discovered) {
+        match self {
+            // The most specific spec that gave a percentage (or, failing
+            // that, a weight) decides; a file spec beats its directory.
+            Self::Explicit => {
+                let rank = |d: &Discovered| {
+                    (
+                        d.source.explicit_percent.is_some(),
+                        d.source.relative_weight.is_some(),
+                    )
+                };
+                let prefer_next = match rank(&next).cmp(&rank(kept)) {
+                    Ordering::Greater => true,
+                    Ordering::Less => false,
+                    Ordering::Equal => next.specificity < kept.specificity,
+                };
+                if prefer_next {
+                    kept.source.explicit_percent = next.source.explicit_percent;
+                    kept.source.directory_percent = next.source.directory_percent;
+                    kept.source.relative_weight = next.source.relative_weight;
+                    kept.specificity = next.specificity;
+                }
+            }
+            Self::Sum => {
+                kept.source.explicit_percent =
+                    sum_options(kept.source.explicit_percent, next.source.explicit_percent);
+                kept.source.directory_percent |= next.source.directory_percent;
+                kept.source.relative_weight =
+                    sum_options(kept.source.relative_weight, next.source.relative_weight);
+            }
+        }
+        kept.source.merged_from += next.source.merged_from;
+    }
 }
 
 #[instrument(skip_all)]
@@ -42,38 +108,175 @@ pub fn discover_weighted_sources(
     };
     debug!(input_specs = raw_specs.len(), "running source discovery");
 
-    let mut out = Vec::new();
+    let mut groups = Vec::new();
     for spec in raw_specs {
-        let discovered = resolve_spec_paths(&spec.path)?;
-        if discovered.is_empty() {
+        let paths: Vec<PathBuf> = resolve_spec_paths(&spec.path, config.auto_index)?
+            .into_iter()
+            .filter(|path| passes_offensive_filter(path, config))
+            .collect();
+        if paths.is_empty() {
             trace!(path = %spec.path.display(), "no sources discovered for spec");
             continue;
         }
-        let share = spec.percent.map(|p| p / (discovered.len() as f64));
-        for path in discovered {
-            if is_offensive(&path) && !config.allow_any && !config.offensive_only {
-                trace!(path = %path.display(), "skipping offensive file in default mode");
-                continue;
+        let keys = paths.iter().map(|path| canonical_key(path)).collect();
+        groups.push(SpecGroup { spec, paths, keys });
+    }
+
+    let parents = nest_specs(&groups);
+    let mut out: Vec<Discovered> = Vec::new();
+    let mut by_key: HashMap<&Path, usize> = HashMap::new();
+    for (idx, group) in groups.iter().enumerate() {
+        let children: Vec<&SpecGroup> = groups
+            .iter()
+            .zip(&parents)
+            .filter(|(_, parent)| **parent == Some(idx))
+            .map(|(child, _)| child)
+            .collect();
+        let own: Vec<(&PathBuf, &PathBuf)> = group
+            .paths
+            .iter()
+            .zip(&group.keys)
+            .filter(|(_, key)| !children.iter().any(|child| child.keys.contains(*key)))
+            .collect();
+        if own.is_empty() {
+            continue;
+        }
+        // Nested percentages are carved out of the parent's share.
+        let share = match group.spec.percent {
+            Some(percent) => {
+                let nested: f64 = children.iter().filter_map(|c| c.spec.percent).sum();
+                if nested > percent + f64::EPSILON {
+                    return Err(Error::NestedPercentExceedsParent {
+                        path: group.spec.path.clone(),
+                        total: nested,
+                        parent: percent,
+                    });
+                }
+                Some((percent - nested).max(0.0) / own.len() as f64)
             }
-            if config.offensive_only && !is_offensive(&path) {
-                trace!(path = %path.display(), "skipping non-offensive file in offensive-only mode");
-                continue;
+            None => None,
+        };
+        let weight = group.spec.weight.map(|w| w / own.len() as f64);
+        let directory_percent = share.is_some()
+            && (group.paths.len() > 1
+                || !children.is_empty()
+                || parents[idx].is_some()
+                || group.spec.path == Path::new("all"));
+        for (path, key) in own {
+            let next = Discovered {
+                source: WeightedSource {
+                    path: path.clone(),
+                    explicit_percent: share,
+                    relative_weight: weight,
+                    merged_from: 1,
+                    directory_percent,
+                },
+                specificity: group.keys.len(),
+            };
+            match by_key.get(key.as_path()) {
+                Some(&slot) => config.overlap.merge(&mut out[slot], next),
+                None => {
+                    by_key.insert(key, out.len());
+                    out.push(next);
+                }
             }
-            out.push(WeightedSource {
-                path,
-                explicit_percent: share,
-            });
         }
     }
 
     if out.is_empty() {
-        bail!("no fortune database files discovered");
+        return Err(Error::NoSources);
+    }
+    for merged in out.iter().filter(|d| d.source.merged_from > 1) {
+        warn!(
+            path = %merged.source.path.display(),
+            specs = merged.source.merged_from,
+            policy = config.overlap.label(),
+            "source is named by several specs; merged into one"
+        );
+    }
+
+    let mut out: Vec<WeightedSource> = out.into_iter().map(|d| d.source).collect();
+    if let Some(schedule) = &config.schedule {
+        schedule.apply(&mut out);
+        if out.is_empty() {
+            return Err(Error::NoSources);
+        }
     }
 
     debug!(discovered = out.len(), "source discovery completed");
     Ok(out)
 }
 
+struct Discovered {
+    source: WeightedSource,
+    specificity: usize,
+}
+
+fn sum_options(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0.0) + b.unwrap_or(0.0)),
+    }
+}
+
+struct SpecGroup {
+    spec: SourceSpec,
+    paths: Vec<PathBuf>,
+    keys: BTreeSet<PathBuf>,
+}
+
+// A percent spec whose files all sit inside a directory (or `all`) spec is
+// nested under the smallest such spec, like `30% dir 10% dir/linux`.
+fn nest_specs(groups: &[SpecGroup]) -> Vec<Option<usize>> {
+    groups
+        .iter()
+        .enumerate()
+        .map(|(idx, child)| {
+            child.spec.percent?;
+            groups
+                .iter()
+                .enumerate()
+                .filter(|(other, parent)| {
+                    *other != idx
+                        && (parent.spec.path == Path::new("all") || parent.spec.path.is_dir())
+                        && child.keys.len() < parent.keys.len()
+                        && child.keys.is_subset(&parent.keys)
+                })
+                .min_by_key(|(_, parent)| parent.keys.len())
+                .map(|(other, _)| other)
+        })
+        .collect()
+}
+
+pub(crate) fn canonical_key(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+pub(crate) fn passes_offensive_filter(path: &Path, config: &DiscoveryConfig) -> bool {
+    if is_offensive(path) && !config.allow_any && !config.offensive_only {
+        trace!(path = %path.display(), "skipping offensive file in default mode");
+        return false;
+    }
+    if config.offensive_only && !is_offensive(path) {
+        trace!(path = %path.display(), "skipping non-offensive file in offensive-only mode");
+        return false;
+    }
+    true
+}
+
+#[cfg(feature = "builtin-corpus")]
+pub fn is_builtin(path: &Path) -> bool {
+    path == Path::new(crate::builtin::BUILTIN_SOURCE_PATH)
+}
+
+// `builtin:` always names the embedded corpus; a bare `builtin` does too,
+// unless a real file or directory by that name is in the way.
+#[cfg(feature = "builtin-corpus")]
+fn names_builtin(spec_path: &Path) -> bool {
+    is_builtin(spec_path)
+        || (spec_path == Path::new(crate::builtin::BUILTIN_SOURCE_NAME) && !spec_path.exists())
+}
+
 fn is_offensive(path: &Path) -> bool {
     path.file_name()
         .and_then(|s| s.to_str())
@@ -81,7 +284,7 @@ fn is_offensive(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
-fn default_source_specs() -> Result<Vec<SourceSpec>> {
+pub(crate) fn default_source_specs() -> Result<Vec<SourceSpec>> {
     let mut out = Vec::new();
     for dir in default_base_dirs() {
         for locale_dir in locale_candidates_for_dir(&dir) {
@@ -89,6 +292,7 @@ fn default_source_specs() -> Result<Vec<SourceSpec>> {
                 out.push(SourceSpec {
                     path: locale_dir,
                     percent: None,
+                    weight: None,
                 });
             }
         }
@@ -96,16 +300,26 @@ fn default_source_specs() -> Result<Vec<SourceSpec>> {
             out.push(SourceSpec {
                 path: dir,
                 percent: None,
+                weight: None,
             });
         }
     }
+    #[cfg(feature = "builtin-corpus")]
     if out.is_empty() {
-        bail!("no default fortune directories found");
+        debug!("no default fortune directories; falling back to builtin corpus");
+        out.push(SourceSpec {
+            path: PathBuf::from(crate::builtin::BUILTIN_SOURCE_PATH),
+            percent: None,
+            weight: None,
+        });
+    }
+    if out.is_empty() {
+        return Err(Error::NoDefaultDirectories);
     }
     Ok(out)
 }
 
-fn default_base_dirs() -> Vec<PathBuf> {
+pub(crate) fn default_base_dirs() -> Vec<PathBuf> {
     let path = env::var("FORTUNE_PATH").unwrap_or_else(|_| DEFAULT_FORTUNE_PATH.to_string());
     path.split(':')
         .filter(|entry| !entry.is_empty())
@@ -132,35 +346,40 @@ fn locale_candidates_for_dir(base_dir: &Path) -> Vec<PathBuf> {
 }
 
 #[instrument(skip_all, fields(spec = %spec_path.display()))]
-fn resolve_spec_paths(spec_path: &Path) -> Result<Vec<PathBuf>> {
+pub(crate) fn resolve_spec_paths(spec_path: &Path, auto_index: AutoIndex) -> Result<Vec<PathBuf>> {
     if spec_path == Path::new("all") {
         let mut dedup = BTreeSet::new();
         for dir in default_base_dirs() {
-            for entry in collect_fortune_files(&dir)? {
+            for entry in collect_fortune_files(&dir, auto_index)? {
                 dedup.insert(entry);
             }
         }
         return Ok(dedup.into_iter().collect());
     }
 
+    #[cfg(feature = "builtin-corpus")]
+    if names_builtin(spec_path) {
+        return Ok(vec![PathBuf::from(crate::builtin::BUILTIN_SOURCE_PATH)]);
+    }
+
     if spec_path.is_dir() {
-        return collect_fortune_files(spec_path);
+        return collect_fortune_files(spec_path, auto_index);
     }
 
     if spec_path.is_file() {
-        if dat_path_for_text(spec_path).is_file() {
+        if is_indexable(spec_path, auto_index) {
             return Ok(vec![spec_path.to_path_buf()]);
         }
-        bail!(
-            "fortune text file '{}' has no .dat sibling",
-            spec_path.display()
-        );
+        return Err(Error::MissingDat {
+            path: spec_path.to_path_buf(),
+        });
     }
 
-    if let Some(alt) = offensive_alternate(spec_path) {
-        if alt.is_file() && dat_path_for_text(&alt).is_file() {
-            return Ok(vec![alt]);
-        }
+    if let Some(alt) = offensive_alternate(spec_path)
+        && alt.is_file()
+        && is_indexable(&alt, auto_index)
+    {
+        return Ok(vec![alt]);
     }
 
     Ok(Vec::new())
@@ -175,12 +394,11 @@ fn offensive_alternate(path: &Path) -> Option<PathBuf> {
 }
 
 #[instrument(skip_all, fields(dir = %dir.display()))]
-fn collect_fortune_files(dir: &Path) -> Result<Vec<PathBuf>> {
-    let entries = fs::read_dir(dir)
-        .with_context(|| format!("failed reading fortune directory {}", dir.display()))?;
+fn collect_fortune_files(dir: &Path, auto_index: AutoIndex) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir).map_err(Error::io("reading fortune directory", dir))?;
     let mut files = Vec::new();
     for entry in entries {
-        let entry = entry?;
+        let entry = entry.map_err(Error::io("reading fortune directory", dir))?;
         let path = entry.path();
         if !path.is_file() {
             continue;
@@ -188,11 +406,10 @@ fn collect_fortune_files(dir: &Path) -> Result<Vec<PathBuf>> {
         let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
             continue;
         };
-        if name.starts_with('.') || name.ends_with(".dat") || name.ends_with(".u8") {
+        if name.starts_with('.') || is_sidecar_file(name) {
             continue;
         }
-        let dat_path = dat_path_for_text(&path);
-        if dat_path.is_file() {
+        if is_indexable_in_dir(&path, auto_index) {
             files.push(path);
         }
     }
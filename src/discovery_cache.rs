@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, instrument, warn};
+
+use crate::auto_index::{AutoIndex, cache_dir};
+use crate::datfile::{LengthFilter, dat_path_for_text};
+use crate::discovery::{
+    DiscoveryConfig, default_base_dirs, default_source_specs, passes_offensive_filter,
+    resolve_spec_paths,
+};
+use crate::error::{Error, Result};
+use crate::fortune_engine::{
+    FileSelectionMode, FortuneSelection, RecordSelection, SourceWeight, Weighting,
+    calculate_weight_probabilities, choose_source_index, open_source, select_record,
+};
+use crate::rng::FortuneRng;
+
+const CACHE_VERSION: u32 = 1;
+const CACHE_FILE_NAME: &str = "discovery.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryCache {
+    version: u32,
+    search_key: String,
+    directories: Vec<DirectoryStamp>,
+    pub sources: Vec<CachedSource>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct DirectoryStamp {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedSource {
+    pub path: PathBuf,
+    pub text_modified: Option<SystemTime>,
+    pub dat_modified: Option<SystemTime>,
+    pub record_count: usize,
+    pub length_histogram: BTreeMap<usize, usize>,
+}
+
+impl CachedSource {
+    pub fn candidate_count(&self, filter: LengthFilter) -> usize {
+        self.length_histogram
+            .iter()
+            .filter(|(len, _)| filter.accepts(**len))
+            .map(|(_, count)| *count)
+            .sum()
+    }
+
+    fn is_current(&self) -> bool {
+        modified(&self.path) == self.text_modified
+            && modified(&dat_path_for_text(&self.path)) == self.dat_modified
+    }
+}
+
+impl DiscoveryCache {
+    pub fn default_path() -> Option<PathBuf> {
+        cache_dir().map(|dir| dir.join(CACHE_FILE_NAME))
+    }
+
+    #[instrument(skip_all, fields(path = %path.display(), force_rebuild))]
+    pub fn load_or_rebuild(path: &Path, force_rebuild: bool) -> Result<Self> {
+        if !force_rebuild {
+            match Self::read(path) {
+                Ok(Some(cache)) if cache.is_current() => {
+                    debug!(sources = cache.sources.len(), "using discovery cache");
+                    return Ok(cache);
+                }
+                Ok(_) => debug!("discovery cache missing or stale"),
+                Err(err) => warn!("ignoring unreadable discovery cache: {err}"),
+            }
+        }
+
+        let cache = Self::build()?;
+        if let Err(err) = cache.write(path) {
+            warn!("failed writing discovery cache: {err}");
+        }
+        Ok(cache)
+    }
+
+    #[instrument]
+    pub fn build() -> Result<Self> {
+        let specs = default_source_specs()?;
+        let mut dirs = BTreeSet::new();
+        dirs.extend(default_base_dirs());
+        dirs.extend(specs.iter().map(|spec| spec.path.clone()));
+        let directories = dirs
+            .into_iter()
+            .map(|path| DirectoryStamp {
+                modified: modified(&path),
+                path,
+            })
+            .collect();
+
+        let mut sources = Vec::new();
+        for spec in &specs {
+            for path in resolve_spec_paths(&spec.path, AutoIndex::Off)? {
+                sources.push(summarize_source(&path)?);
+            }
+        }
+        debug!(sources = sources.len(), "rebuilt discovery cache");
+        Ok(Self {
+            version: CACHE_VERSION,
+            search_key: search_key(),
+            directories,
+            sources,
+        })
+    }
+
+    pub fn is_current(&self) -> bool {
+        self.version == CACHE_VERSION
+            && self.search_key == search_key()
+            && self
+                .directories
+                .iter()
+                .all(|dir| modified(&dir.path) == dir.modified)
+            && self.sources.iter().all(CachedSource::is_current)
+    }
+
+    #[instrument(skip_all)]
+    pub fn select_random_fortune(
+        &self,
+        config: &DiscoveryConfig,
+        length_filter: LengthFilter,
+        weighting: Weighting,
+        records: RecordSelection,
+        rng: &mut FortuneRng,
+    ) -> Result<FortuneSelection> {
+        let discovered: Vec<&CachedSource> = self
+            .sources
+            .iter()
+            .filter(|source| passes_offensive_filter(&source.path, config))
+            .collect();
+        if discovered.is_empty() {
+            return Err(Error::NoSources);
+        }
+
+        let mut eligible = Vec::new();
+        let mut weights = Vec::new();
+        for source in discovered {
+            let candidate_count = source.candidate_count(length_filter);
+            if candidate_count > 0 {
+                eligible.push(source);
+                weights.push(SourceWeight {
+                    candidate_count,
+                    ..SourceWeight::default()
+                });
+            }
+        }
+        if eligible.is_empty() {
+            return Err(Error::FilterLeftNothing);
+        }
+
+        let probabilities = calculate_weight_probabilities(&weights, weighting)?;
+        let mode = FileSelectionMode::for_weights(&weights, weighting);
+        let chosen = eligible[choose_source_index(&weights, &probabilities, rng, mode)?];
+        let source = open_source(&chosen.path, AutoIndex::Off)?;
+        let candidate_indices = source.candidate_indices(length_filter)?;
+        select_record(source.as_ref(), &candidate_indices, rng, records)
+    }
+
+    fn read(path: &Path) -> Result<Option<Self>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::io("reading", path)(err)),
+        };
+        let cache = serde_json::from_slice(&bytes).map_err(|source| Error::Cache {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Some(cache))
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(Error::io("creating", parent))?;
+        }
+        // Write-then-rename so concurrent invocations never observe a torn cache.
+        let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        let encoded = serde_json::to_vec(self).map_err(|source| Error::Cache {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        fs::write(&tmp, encoded).map_err(Error::io("writing", &tmp))?;
+        fs::rename(&tmp, path).map_err(Error::io("replacing", path))
+    }
+}
+
+fn summarize_source(path: &Path) -> Result<CachedSource> {
+    let source = open_source(path, AutoIndex::Off)?;
+    let mut length_histogram = BTreeMap::new();
+    for idx in 0..source.record_count() {
+        *length_histogram
+            .entry(source.record_bytes(idx)?.len())
+            .or_insert(0) += 1;
+    }
+    // Virtual sources have no files to stamp; they only change with the binary.
+    Ok(CachedSource {
+        text_modified: modified(path),
+        dat_modified: modified(&dat_path_for_text(path)),
+        record_count: source.record_count(),
+        length_histogram,
+        path: path.to_path_buf(),
+    })
+}
+
+fn search_key() -> String {
+    format!(
+        "{};FORTUNE_PATH={:?};LANG={:?}",
+        env!("CARGO_PKG_VERSION"),
+        env::var("FORTUNE_PATH").ok(),
+        env::var("LANG").ok()
+    )
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
@@ -0,0 +1,152 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::datfile::DatValidationError;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NO_SOURCES: i32 = 3;
+pub const EXIT_NO_RECORDS: i32 = 4;
+pub const EXIT_DATA: i32 = 5;
+pub const EXIT_IO: i32 = 6;
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("failed {action} {}", path.display())]
+    Io {
+        action: &'static str,
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("invalid dat data")]
+    InvalidDat(#[from] DatValidationError),
+    #[error("corrupt dat file {}", path.display())]
+    CorruptDat {
+        path: PathBuf,
+        #[source]
+        source: DatValidationError,
+    },
+    #[error("offset {offset} is out of range for file {file_len} bytes in {}", path.display())]
+    OffsetOutOfRange {
+        path: PathBuf,
+        offset: u32,
+        file_len: u64,
+    },
+    #[error("record index {index} out of range")]
+    RecordOutOfRange { index: usize },
+    #[error("invalid record span [{start}, {end}) for file size {file_len}")]
+    InvalidSpan {
+        start: usize,
+        end: usize,
+        file_len: usize,
+    },
+    #[error("no fortune records were parsed")]
+    EmptyCorpus,
+    #[error("record start offset {offset} exceeds STRFILE u32")]
+    OffsetOverflow { offset: usize },
+    #[error("--order and --random cannot be used together")]
+    ConflictingOrder,
+    #[error("fortune text file '{}' has no .dat sibling", path.display())]
+    MissingDat { path: PathBuf },
+    #[error("no default fortune directories found")]
+    NoDefaultDirectories,
+    #[error("no fortune database files discovered")]
+    NoSources,
+    #[error("no fortune records are available after filtering")]
+    FilterLeftNothing,
+    #[error("selected source '{}' has no records matching filters", path.display())]
+    NoMatchingRecords { path: PathBuf },
+    #[error("fortune file '{}' has no records", path.display())]
+    NoRecords { path: PathBuf },
+    #[error("specified percentages exceed 100% (got {total:.3}%)")]
+    PercentagesExceed100 { total: f64 },
+    #[error("percentages inside '{}' add up to {total:.3}%, more than its {parent:.3}%", path.display())]
+    NestedPercentExceedsParent {
+        path: PathBuf,
+        total: f64,
+        parent: f64,
+    },
+    #[error("percentage out of range 0..=100: {percent}")]
+    PercentOutOfRange { percent: f64 },
+    #[error("invalid percentage value '{value}'")]
+    InvalidPercent { value: String },
+    #[error("invalid relative weight '{value}' (expected a positive number)")]
+    InvalidWeight { value: String },
+    #[error("missing path after percentage token '{token}'")]
+    MissingPercentPath { token: String },
+    #[error("computed source probabilities are all zero")]
+    ZeroProbability,
+    #[error("invalid regex")]
+    InvalidRegex(#[from] regex::Error),
+    #[error("{0}")]
+    InvalidArgument(String),
+    #[error("cannot import {}: {reason}", path.display())]
+    InvalidImport { path: PathBuf, reason: String },
+    #[error("invalid config {}: {reason}", path.display())]
+    InvalidConfig { path: PathBuf, reason: String },
+    #[error("invalid discovery cache {}", path.display())]
+    Cache {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+impl Error {
+    pub fn io(action: &'static str, path: &Path) -> impl FnOnce(io::Error) -> Self {
+        let path = path.to_path_buf();
+        move |source| Self::Io {
+            action,
+            path,
+            source,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Io { .. } => EXIT_IO,
+            Self::InvalidDat(_)
+            | Self::CorruptDat { .. }
+            | Self::OffsetOutOfRange { .. }
+            | Self::RecordOutOfRange { .. }
+            | Self::InvalidSpan { .. }
+            | Self::EmptyCorpus
+            | Self::OffsetOverflow { .. }
+            | Self::NoRecords { .. }
+            | Self::InvalidImport { .. }
+            | Self::Cache { .. } => EXIT_DATA,
+            Self::MissingDat { .. } | Self::NoDefaultDirectories | Self::NoSources => {
+                EXIT_NO_SOURCES
+            }
+            Self::FilterLeftNothing | Self::NoMatchingRecords { .. } => EXIT_NO_RECORDS,
+            Self::ConflictingOrder
+            | Self::PercentagesExceed100 { .. }
+            | Self::NestedPercentExceedsParent { .. }
+            | Self::PercentOutOfRange { .. }
+            | Self::InvalidPercent { .. }
+            | Self::InvalidWeight { .. }
+            | Self::MissingPercentPath { .. }
+            | Self::ZeroProbability
+            | Self::InvalidRegex(_)
+            | Self::InvalidArgument(_)
+            | Self::InvalidConfig { .. } => EXIT_USAGE,
+        }
+    }
+}
+
+pub fn exit_code_for(err: &(dyn std::error::Error + 'static)) -> i32 {
+    let mut current = Some(err);
+    while let Some(err) = current {
+        if let Some(err) = err.downcast_ref::<Error>() {
+            return err.exit_code();
+        }
+        current = err.source();
+    }
+    EXIT_FAILURE
+}
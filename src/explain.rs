@@ -0,0 +1,255 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::Serialize;
+use tracing::instrument;
+
+use crate::auto_index::{AutoIndex, is_indexable_in_dir};
+use crate::datfile::is_sidecar_file;
+use crate::discovery::{
+    DiscoveryConfig, default_base_dirs, default_source_specs, passes_offensive_filter,
+    resolve_spec_paths,
+};
+use crate::error::{Error, Result};
+use crate::fortune_engine::{DrawTrace, FileSelectionMode, LoadedSource, Weighting};
+use crate::sources::SourceSpec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExplainFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Explanation {
+    pub sources: Vec<SourceDecision>,
+    pub mode: SelectionMode,
+    pub weighting: Weighting,
+    pub probabilities: Vec<SourceProbability>,
+    pub draw: DrawTrace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceDecision {
+    pub path: PathBuf,
+    pub status: SourceStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SourceStatus {
+    Loaded,
+    Offensive,
+    OffSchedule,
+    NoDat,
+    NoCandidates,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SelectionMode {
+    ProbabilityPercent,
+    WeightedDraw,
+    CandidateCount,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourceProbability {
+    pub source: String,
+    pub percent: f64,
+    pub explicit_percent: Option<f64>,
+    pub relative_weight: Option<f64>,
+    pub candidates: usize,
+}
+
+impl FromStr for ExplainFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(Error::InvalidArgument(format!(
+                "unknown explain format '{other}' (expected text|json)"
+            ))),
+        }
+    }
+}
+
+impl SourceStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Loaded => "loaded",
+            Self::Offensive => "offensive",
+            Self::OffSchedule => "off-schedule",
+            Self::NoDat => "no-dat",
+            Self::NoCandidates => "no-candidates",
+        }
+    }
+}
+
+impl SelectionMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::ProbabilityPercent => "probability-percent",
+            Self::WeightedDraw => "weighted-draw",
+            Self::CandidateCount => "candidate-count",
+        }
+    }
+}
+
+impl From<FileSelectionMode> for SelectionMode {
+    fn from(mode: FileSelectionMode) -> Self {
+        match mode {
+            FileSelectionMode::ProbabilityPercent => Self::ProbabilityPercent,
+            FileSelectionMode::WeightedDraw => Self::WeightedDraw,
+            FileSelectionMode::CandidateCount => Self::CandidateCount,
+        }
+    }
+}
+
+pub fn source_probabilities(
+    loaded: &[LoadedSource],
+    probabilities: &[f64],
+) -> Vec<SourceProbability> {
+    loaded
+        .iter()
+        .zip(probabilities)
+        .map(|(entry, percent)| SourceProbability {
+            source: entry.source.display_name(),
+            percent: *percent,
+            explicit_percent: entry.explicit_percent,
+            relative_weight: entry.relative_weight,
+            candidates: entry.candidate_indices.len(),
+        })
+        .collect()
+}
+
+// Re-walks discovery for the file specs and classifies every path it saw
+// against what actually got loaded.
+#[instrument(skip_all)]
+pub fn source_decisions(
+    specs: &[SourceSpec],
+    config: &DiscoveryConfig,
+    loaded: &[LoadedSource],
+) -> Result<Vec<SourceDecision>> {
+    let file_specs: Vec<SourceSpec> = specs
+        .iter()
+        .filter(|spec| spec.tag().is_none())
+        .cloned()
+        .collect();
+    let file_specs = if file_specs.is_empty() {
+        default_source_specs()?
+    } else {
+        file_specs
+    };
+    let loaded_paths: HashSet<PathBuf> = loaded
+        .iter()
+        .filter_map(|entry| entry.source.metadata().path)
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for spec in &file_specs {
+        let dirs = if spec.path == Path::new("all") {
+            default_base_dirs()
+        } else if spec.path.is_dir() {
+            vec![spec.path.clone()]
+        } else {
+            Vec::new()
+        };
+        for path in dirs
+            .iter()
+            .flat_map(|dir| unindexed_files(dir, config.auto_index))
+        {
+            if seen.insert(path.clone()) {
+                out.push(SourceDecision {
+                    path,
+                    status: SourceStatus::NoDat,
+                });
+            }
+        }
+        for path in resolve_spec_paths(&spec.path, config.auto_index)? {
+            let status = if !passes_offensive_filter(&path, config) {
+                SourceStatus::Offensive
+            } else if config
+                .schedule
+                .as_ref()
+                .is_some_and(|schedule| !schedule.admits(&path))
+            {
+                SourceStatus::OffSchedule
+            } else if loaded_paths.contains(&path) {
+                SourceStatus::Loaded
+            } else {
+                SourceStatus::NoCandidates
+            };
+            if seen.insert(path.clone()) {
+                out.push(SourceDecision { path, status });
+            }
+        }
+    }
+    out.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(out)
+}
+
+fn unindexed_files(dir: &Path, auto_index: AutoIndex) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut out: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| !name.starts_with('.') && !is_sidecar_file(name))
+        })
+        .filter(|path| !is_indexable_in_dir(path, auto_index))
+        .collect();
+    out.sort();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::datfile::LengthFilter;
+    use crate::fortune_engine::{RecordSelection, select_random_fortune_traced};
+    use crate::fortune_source::MemorySource;
+    use crate::rng::FortuneRng;
+
+    #[test]
+    fn traced_selection_records_marker_walk_and_record_skips() {
+        let load = |name: &str, records: &[&str], percent| {
+            let source = Arc::new(MemorySource::new(name, records.iter().copied()));
+            LoadedSource::new(source, percent, LengthFilter::Short { threshold: 3 }).expect("load")
+        };
+        let entries = [
+            load("a", &["one"], Some(0.0)),
+            load("b", &["long one", "long two", "abc"], None),
+        ];
+        let mut trace = DrawTrace::default();
+        let pick = select_random_fortune_traced(
+            &entries,
+            &[0.0, 100.0],
+            &mut FortuneRng::seeded(1),
+            FileSelectionMode::ProbabilityPercent,
+            RecordSelection::Parity,
+            &mut trace,
+        )
+        .expect("pick");
+        assert_eq!(pick.text, "abc");
+        assert_eq!(trace.chosen_source, 1);
+        assert_eq!(trace.walk.len(), 2);
+        assert_eq!(trace.walk[1].marker, trace.source_draw as f64);
+        assert_eq!(trace.record_total, 3);
+        assert_eq!(trace.initial_index, (trace.record_draw + 1) % 3);
+        assert_eq!(trace.record_index, 2);
+        assert_eq!(trace.skipped_records, (2 + 3 - trace.initial_index) % 3);
+    }
+}
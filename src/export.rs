@@ -0,0 +1,178 @@
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Serialize;
+use tracing::{debug, instrument};
+
+use crate::auto_index::{AutoIndex, open_fortune_file};
+use crate::datfile::{FortuneFile, STR_COMMENTS, STR_ROTATED};
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Ndjson,
+    Csv,
+    Markdown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportRecord {
+    pub index: usize,
+    pub source: String,
+    pub length: usize,
+    pub offset: u32,
+    pub text: String,
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            "csv" => Ok(Self::Csv),
+            "markdown" | "md" => Ok(Self::Markdown),
+            other => Err(Error::InvalidArgument(format!(
+                "unknown export format '{other}' (expected ndjson|csv|markdown)"
+            ))),
+        }
+    }
+}
+
+pub fn open_export_file(path: &Path, auto_index: AutoIndex) -> Result<FortuneFile> {
+    #[cfg(feature = "builtin-corpus")]
+    if crate::discovery::is_builtin(path) {
+        return Ok(crate::builtin::BuiltinSource::load()?.into_file());
+    }
+    open_fortune_file(path, auto_index)
+}
+
+#[instrument(skip_all, fields(path = %db.text_path.display()))]
+pub fn export_records(db: &FortuneFile) -> Result<Vec<ExportRecord>> {
+    let flags = db.dat.header.flags;
+    let source = db.text_path.display().to_string();
+    let mut out = Vec::with_capacity(db.record_count());
+    for (index, offset) in db.dat.offsets.iter().enumerate() {
+        let bytes = db.record_bytes(index)?;
+        let mut text = String::from_utf8_lossy(bytes).into_owned();
+        if flags & STR_COMMENTS != 0 {
+            text = strip_comment_lines(&text, db.dat.header.delim);
+        }
+        if flags & STR_ROTATED != 0 {
+            text = rot13(&text);
+        }
+        out.push(ExportRecord {
+            index,
+            source: source.clone(),
+            length: bytes.len(),
+            offset: *offset,
+            text,
+        });
+    }
+    debug!(records = out.len(), flags, "collected export records");
+    Ok(out)
+}
+
+pub fn write_records(
+    out: &mut dyn Write,
+    format: ExportFormat,
+    records: &[ExportRecord],
+) -> io::Result<()> {
+    match format {
+        ExportFormat::Ndjson => {
+            for record in records {
+                serde_json::to_writer(&mut *out, record)?;
+                out.write_all(b"\n")?;
+            }
+        }
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(&mut *out);
+            for record in records {
+                writer.serialize(record)?;
+            }
+            writer.flush()?;
+        }
+        ExportFormat::Markdown => {
+            let mut current_source = None;
+            for record in records {
+                if current_source != Some(&record.source) {
+                    writeln!(out, "## {}\n", record.source)?;
+                    current_source = Some(&record.source);
+                }
+                let fence = code_fence_for(&record.text);
+                writeln!(
+                    out,
+                    "### {} (offset {}, {} bytes)\n\n{fence}text\n{}{}{fence}\n",
+                    record.index,
+                    record.offset,
+                    record.length,
+                    record.text,
+                    if record.text.ends_with('\n') {
+                        ""
+                    } else {
+                        "\n"
+                    },
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn rot13(text: &str) -> String {
+    text.chars()
+        .map(|ch| match ch {
+            'a'..='z' => (((ch as u8 - b'a') + 13) % 26 + b'a') as char,
+            'A'..='Z' => (((ch as u8 - b'A') + 13) % 26 + b'A') as char,
+            other => other,
+        })
+        .collect()
+}
+
+fn strip_comment_lines(text: &str, delim: u8) -> String {
+    let marker = [delim, delim];
+    text.split_inclusive('\n')
+        .filter(|line| !line.as_bytes().starts_with(&marker))
+        .collect()
+}
+
+fn code_fence_for(text: &str) -> String {
+    let longest = text
+        .lines()
+        .map(|line| {
+            line.trim_start()
+                .chars()
+                .take_while(|ch| *ch == '`')
+                .count()
+        })
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strfile_builder::{BuildOptions, build_dat_from_text};
+
+    #[test]
+    fn export_honors_rotation_and_comments() {
+        let text = b"Uryyb\n%%ab pbzzrag\n%\nJbeyq\n".to_vec();
+        let (mut dat, _) = build_dat_from_text(&text, BuildOptions::default()).expect("build dat");
+        dat.header.flags |= STR_ROTATED | STR_COMMENTS;
+        let db = FortuneFile::from_parts(Path::new("rot"), None, dat, text).expect("open");
+
+        let records = export_records(&db).expect("export");
+        assert_eq!(records[0].text, "Hello\n");
+        assert_eq!(records[1].offset, 21);
+
+        let mut out = Vec::new();
+        write_records(&mut out, ExportFormat::Csv, &records).expect("csv");
+        assert_eq!(
+            String::from_utf8(out).expect("utf8"),
+            "index,source,length,offset,text\n0,rot,19,0,\"Hello\n\"\n1,rot,6,21,\"World\n\"\n"
+        );
+    }
+}
@@ -0,0 +1,638 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use regex::Regex;
+use tracing::{debug, instrument, trace, warn};
+
+use crate::auto_index::AutoIndex;
+use crate::datfile::LengthFilter;
+use crate::discovery::{DiscoveryConfig, Overlap, canonical_key, discover_weighted_sources};
+use crate::error::{Error, Result};
+use crate::fortune_engine::{
+    DrawTrace, FileSelectionMode, FortuneSelection, LoadedSource, MatchRecord, RecordSelection,
+    Weighting, calculate_probabilities, collect_matches, load_sources, select_random_fortune,
+    select_random_fortune_traced, select_record,
+};
+use crate::fortune_source::FortuneSource;
+use crate::layout::Layout;
+use crate::record::RecordFilter;
+use crate::rng::FortuneRng;
+use crate::schedule::Schedule;
+use crate::sources::{SourceSpec, parse_source_specs};
+use crate::tag_index::TaggedSource;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Offensive {
+    #[default]
+    Exclude,
+    Include,
+    Only,
+}
+
+impl Offensive {
+    pub fn admits(self, offensive: bool) -> bool {
+        match self {
+            Self::Exclude => !offensive,
+            Self::Include => true,
+            Self::Only => offensive,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FortuneBuilder {
+    sources: Vec<String>,
+    offensive: Offensive,
+    length: LengthFilter,
+    records: RecordFilter,
+    weighting: Weighting,
+    auto_index: AutoIndex,
+    overlap: Overlap,
+    schedule: Option<Schedule>,
+    record_selection: RecordSelection,
+    fit: Option<(Layout, bool)>,
+    seed: Option<u64>,
+    custom: Vec<(Arc<dyn FortuneSource>, Option<f64>)>,
+}
+
+#[derive(Debug)]
+pub struct Fortune {
+    specs: Vec<SourceSpec>,
+    sources: Vec<LoadedSource>,
+    probabilities: Vec<f64>,
+    mode: FileSelectionMode,
+    weighting: Weighting,
+    record_selection: RecordSelection,
+    rng: FortuneRng,
+}
+
+#[derive(Debug)]
+pub struct Picks<'a> {
+    fortune: &'a mut Fortune,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Replacement {
+    With,
+    #[default]
+    Without,
+}
+
+#[derive(Debug)]
+pub enum Sample<'a> {
+    With(Picks<'a>),
+    Without(UniquePicks<'a>),
+}
+
+// A record identified by the file it is stored in, so the same record
+// reached through a `tag:` view and through its file is one record.
+type RecordKey = (PathBuf, usize);
+
+#[derive(Debug)]
+pub struct UniquePicks<'a> {
+    fortune: &'a mut Fortune,
+    seen: HashSet<RecordKey>,
+    source_ids: HashMap<String, PathBuf>,
+    total: usize,
+}
+
+impl FortuneBuilder {
+    pub fn sources<I, S>(mut self, sources: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.sources = sources.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn offensive(mut self, offensive: Offensive) -> Self {
+        self.offensive = offensive;
+        self
+    }
+
+    pub fn length(mut self, length: LengthFilter) -> Self {
+        self.length = length;
+        self
+    }
+
+    pub fn records(mut self, records: RecordFilter) -> Self {
+        self.records = records;
+        self
+    }
+
+    pub fn equal(mut self, equal: bool) -> Self {
+        self.weighting = if equal {
+            Weighting::Equal
+        } else {
+            Weighting::Count
+        };
+        self
+    }
+
+    pub fn weighting(mut self, weighting: Weighting) -> Self {
+        self.weighting = weighting;
+        self
+    }
+
+    pub fn auto_index(mut self, auto_index: AutoIndex) -> Self {
+        self.auto_index = auto_index;
+        self
+    }
+
+    pub fn overlap(mut self, overlap: Overlap) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    pub fn schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = Some(schedule);
+        self
+    }
+
+    pub fn record_selection(mut self, record_selection: RecordSelection) -> Self {
+        self.record_selection = record_selection;
+        self
+    }
+
+    // With `Overflow::Skip`, records taller than the layout's line limit
+    // (rendered with or without attribution) are never selected.
+    pub fn fit(mut self, layout: Layout, with_attribution: bool) -> Self {
+        self.fit = Some((layout, with_attribution));
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn source(mut self, source: impl FortuneSource + 'static, percent: Option<f64>) -> Self {
+        self.custom.push((Arc::new(source), percent));
+        self
+    }
+
+    #[instrument(skip_all)]
+    pub fn build(self) -> Result<Fortune> {
+        let specs = parse_source_specs(&self.sources)?;
+        let config = DiscoveryConfig {
+            allow_any: self.offensive == Offensive::Include,
+            offensive_only: self.offensive == Offensive::Only,
+            auto_index: self.auto_index,
+            overlap: self.overlap,
+            schedule: self.schedule,
+        };
+        let (tag_specs, file_specs): (Vec<SourceSpec>, Vec<SourceSpec>) =
+            specs.iter().cloned().partition(|spec| spec.tag().is_some());
+        // Custom backends replace the default search path unless file specs are also given.
+        let mut sources = if file_specs.is_empty() && !self.custom.is_empty() {
+            Vec::new()
+        } else {
+            let discovered = discover_weighted_sources(&file_specs, &config)?;
+            load_sources(&discovered, self.length, self.auto_index)?
+        };
+        for (source, percent) in self.custom {
+            if !self.offensive.admits(source.metadata().offensive) {
+                trace!(source = %source.display_name(), "skipping custom source by offensive policy");
+                continue;
+            }
+            sources.push(LoadedSource::new(source, percent, self.length)?);
+        }
+        for loaded in &mut sources {
+            loaded.retain_records(&self.records)?;
+            if let Some((layout, with_attribution)) = &self.fit {
+                loaded.retain_fitting(layout, *with_attribution)?;
+            }
+        }
+        sources.retain(|loaded| !loaded.candidate_indices.is_empty());
+        if !tag_specs.is_empty() {
+            // Tag specs draw from the discovered pool; it is only selectable itself when
+            // file specs were given alongside them.
+            let pool = if file_specs.is_empty() {
+                std::mem::take(&mut sources)
+            } else {
+                sources.clone()
+            };
+            for spec in &tag_specs {
+                let tag = spec.tag().unwrap_or_default();
+                let view = TaggedSource::collect(tag, &pool)?;
+                if view.record_count() == 0 {
+                    warn!(tag, "no records carry this tag");
+                    continue;
+                }
+                sources.push(
+                    LoadedSource::new(Arc::new(view), spec.percent, LengthFilter::Any)?
+                        .with_weight(spec.weight),
+                );
+            }
+        }
+        if sources.is_empty() {
+            return Err(Error::FilterLeftNothing);
+        }
+        let probabilities = calculate_probabilities(&sources, self.weighting)?;
+        let mode = FileSelectionMode::for_sources(&sources, self.weighting);
+        let rng = match self.seed {
+            Some(seed) => FortuneRng::seeded(seed),
+            None => FortuneRng::from_env()?,
+        };
+        debug!(sources = sources.len(), ?mode, "built fortune facade");
+        Ok(Fortune {
+            specs,
+            sources,
+            probabilities,
+            mode,
+            weighting: self.weighting,
+            record_selection: self.record_selection,
+            rng,
+        })
+    }
+}
+
+impl Fortune {
+    pub fn builder() -> FortuneBuilder {
+        FortuneBuilder::default()
+    }
+
+    pub fn pick(&mut self) -> Result<FortuneSelection> {
+        select_random_fortune(
+            &self.sources,
+            &self.probabilities,
+            &mut self.rng,
+            self.mode,
+            self.record_selection,
+        )
+    }
+
+    pub fn pick_traced(&mut self) -> Result<(FortuneSelection, DrawTrace)> {
+        let mut trace = DrawTrace::default();
+        let selection = select_random_fortune_traced(
+            &self.sources,
+            &self.probabilities,
+            &mut self.rng,
+            self.mode,
+            self.record_selection,
+            &mut trace,
+        )?;
+        Ok((selection, trace))
+    }
+
+    pub fn search(&self, regex: &Regex) -> Result<Vec<MatchRecord>> {
+        collect_matches(&self.sources, regex)
+    }
+
+    pub fn iter(&mut self) -> Picks<'_> {
+        Picks { fortune: self }
+    }
+
+    pub fn unique(&mut self) -> UniquePicks<'_> {
+        let mut source_ids = HashMap::new();
+        let total = self
+            .reachable()
+            .flat_map(|entry| {
+                entry
+                    .candidate_indices
+                    .iter()
+                    .map(|idx| (entry.source.as_ref(), *idx))
+            })
+            .map(|(source, idx)| record_key(source, idx, &mut source_ids))
+            .collect::<HashSet<_>>()
+            .len();
+        UniquePicks {
+            fortune: self,
+            seen: HashSet::new(),
+            source_ids,
+            total,
+        }
+    }
+
+    pub fn sample(&mut self, replacement: Replacement) -> Sample<'_> {
+        match replacement {
+            Replacement::With => Sample::With(self.iter()),
+            Replacement::Without => Sample::Without(self.unique()),
+        }
+    }
+
+    pub fn specs(&self) -> &[SourceSpec] {
+        &self.specs
+    }
+
+    pub fn sources(&self) -> &[LoadedSource] {
+        &self.sources
+    }
+
+    pub fn probabilities(&self) -> &[f64] {
+        &self.probabilities
+    }
+
+    pub fn selection_mode(&self) -> FileSelectionMode {
+        self.mode
+    }
+
+    pub fn weighting(&self) -> Weighting {
+        self.weighting
+    }
+
+    pub fn record_selection(&self) -> RecordSelection {
+        self.record_selection
+    }
+
+    fn reachable(&self) -> impl Iterator<Item = &LoadedSource> {
+        self.sources
+            .iter()
+            .zip(&self.probabilities)
+            .filter(|(_, probability)| **probability > 0.0)
+            .map(|(entry, _)| entry)
+    }
+}
+
+impl Iterator for Picks<'_> {
+    type Item = Result<FortuneSelection>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.fortune.pick())
+    }
+}
+
+impl UniquePicks<'_> {
+    pub fn remaining(&self) -> usize {
+        self.total - self.seen.len()
+    }
+
+    pub fn reset(&mut self) {
+        self.seen.clear();
+    }
+
+    fn key_of(&mut self, pick: &FortuneSelection) -> RecordKey {
+        let source = self
+            .fortune
+            .sources
+            .iter()
+            .find(|entry| entry.source.display_name() == pick.source_name)
+            .map(|entry| Arc::clone(&entry.source));
+        match source {
+            Some(source) => record_key(source.as_ref(), pick.record_index, &mut self.source_ids),
+            None => (PathBuf::from(&pick.source_name), pick.record_index),
+        }
+    }
+
+    // Once rejection sampling stops finding fresh records, draw uniformly
+    // from whatever has not been shown yet.
+    fn pick_unseen(&mut self) -> Result<FortuneSelection> {
+        let mut unseen: Vec<(Arc<dyn FortuneSource>, usize)> = Vec::new();
+        let mut keys = HashSet::new();
+        for entry in self.fortune.reachable() {
+            for idx in &entry.candidate_indices {
+                let key = record_key(entry.source.as_ref(), *idx, &mut self.source_ids);
+                if !self.seen.contains(&key) && keys.insert(key) {
+                    unseen.push((Arc::clone(&entry.source), *idx));
+                }
+            }
+        }
+        if unseen.is_empty() {
+            return Err(Error::FilterLeftNothing);
+        }
+        let (source, idx) = &unseen[self.fortune.rng.next_index(unseen.len())];
+        select_record(
+            source.as_ref(),
+            &[*idx],
+            &mut self.fortune.rng,
+            RecordSelection::Fair,
+        )
+    }
+}
+
+impl Iterator for UniquePicks<'_> {
+    type Item = Result<FortuneSelection>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining() == 0 {
+            return None;
+        }
+        let attempts = self.total.saturating_mul(4).max(32);
+        for _ in 0..attempts {
+            let pick = match self.fortune.pick() {
+                Ok(pick) => pick,
+                Err(err) => return Some(Err(err)),
+            };
+            let key = self.key_of(&pick);
+            if self.seen.insert(key) {
+                return Some(Ok(pick));
+            }
+            trace!(source = %pick.source_name, index = pick.record_index, "skipping repeat");
+        }
+        let pick = self.pick_unseen();
+        if let Ok(pick) = &pick {
+            let key = self.key_of(pick);
+            self.seen.insert(key);
+        }
+        Some(pick)
+    }
+}
+
+fn record_key(
+    source: &dyn FortuneSource,
+    index: usize,
+    source_ids: &mut HashMap<String, PathBuf>,
+) -> RecordKey {
+    let (source, index) = source.origin(index).unwrap_or((source, index));
+    let id = source_ids
+        .entry(source.display_name())
+        .or_insert_with_key(|name| match source.metadata().path {
+            Some(path) => canonical_key(&path),
+            None => PathBuf::from(name),
+        });
+    (id.clone(), index)
+}
+
+impl Iterator for Sample<'_> {
+    type Item = Result<FortuneSelection>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::With(picks) => picks.next(),
+            Self::Without(picks) => picks.next(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::fortune_engine::{SourceWeight, calculate_weight_probabilities};
+    use crate::fortune_source::MemorySource;
+    use crate::strfile_builder::{BuildOptions, build_dat_from_text};
+
+    #[test]
+    fn builder_loads_and_picks_with_seed() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("alpha");
+        let text = b"one\n%\ntwo\n%\nthree\n";
+        fs::write(&path, text).expect("write text");
+        let (dat, _) = build_dat_from_text(text, BuildOptions::default()).expect("build dat");
+        dat.write_to_path(&tmp.path().join("alpha.dat"))
+            .expect("write dat");
+
+        let build = || {
+            Fortune::builder()
+                .sources([path.display().to_string()])
+                .seed(7)
+                .build()
+                .expect("build fortune")
+        };
+        let mut fortune = build();
+        assert_eq!(fortune.probabilities(), &[100.0]);
+        assert_eq!(fortune.selection_mode(), FileSelectionMode::CandidateCount);
+
+        let first: Vec<String> = fortune
+            .iter()
+            .take(5)
+            .map(|pick| pick.expect("pick").text)
+            .collect();
+        let second: Vec<String> = build()
+            .iter()
+            .take(5)
+            .map(|pick| pick.expect("pick").text)
+            .collect();
+        assert_eq!(first, second);
+
+        let regex = Regex::new("^t").expect("regex");
+        assert_eq!(fortune.search(&regex).expect("search").len(), 2);
+    }
+
+    #[test]
+    fn builder_selects_over_custom_sources() {
+        let mut fortune = Fortune::builder()
+            .source(MemorySource::new("quotes", ["a", "b"]), Some(75.0))
+            .source(MemorySource::new("more", ["c"]), None)
+            .seed(1)
+            .build()
+            .expect("build fortune");
+        assert_eq!(fortune.probabilities(), &[75.0, 25.0]);
+        assert_eq!(
+            fortune.selection_mode(),
+            FileSelectionMode::ProbabilityPercent
+        );
+        let pick = fortune.pick().expect("pick");
+        assert!(pick.source_path.is_none());
+        assert!(["quotes", "more"].contains(&pick.source_name.as_str()));
+    }
+
+    #[test]
+    fn weighting_strategies_shape_probabilities() {
+        let probabilities = |weighting| {
+            Fortune::builder()
+                .source(MemorySource::new("many", ["a", "b", "c", "d"]), None)
+                .source(MemorySource::new("one", ["long record"]), None)
+                .weighting(weighting)
+                .seed(1)
+                .build()
+                .expect("build fortune")
+                .probabilities()
+                .to_vec()
+        };
+        let cases = [
+            (Weighting::Count, 80.0),
+            (Weighting::Equal, 50.0),
+            (Weighting::Sqrt, 200.0 / 3.0),
+            (Weighting::Bytes, 400.0 / 15.0),
+        ];
+        for (weighting, first) in cases {
+            let probs = probabilities(weighting);
+            assert!((probs[0] - first).abs() < 1e-9, "{weighting:?}: {probs:?}");
+            assert!(
+                (probs[1] - (100.0 - first)).abs() < 1e-9,
+                "{weighting:?}: {probs:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn weighted_draws_keep_shares_under_one_percent() {
+        let mut fortune = Fortune::builder()
+            .source(MemorySource::new("big", ["x".repeat(999)]), None)
+            .source(MemorySource::new("tiny", ["y"]), None)
+            .weighting(Weighting::Bytes)
+            .seed(5)
+            .build()
+            .expect("build fortune");
+        assert_eq!(fortune.selection_mode(), FileSelectionMode::WeightedDraw);
+        assert!((fortune.probabilities()[1] - 0.1).abs() < 1e-9);
+        let tiny = (0..20_000)
+            .filter(|_| fortune.pick().expect("pick").source_name == "tiny")
+            .count();
+        assert!((5..60).contains(&tiny), "tiny picked {tiny} times");
+    }
+
+    #[test]
+    fn only_directory_percentages_scale_up_a_remainder() {
+        let weight = |percent, directory_percent| SourceWeight {
+            explicit_percent: Some(percent),
+            directory_percent,
+            ..SourceWeight::default()
+        };
+        let files = [weight(30.0, false), weight(20.0, false)];
+        assert_eq!(
+            calculate_weight_probabilities(&files, Weighting::Count).expect("probabilities"),
+            [30.0, 20.0]
+        );
+        let spread = [weight(15.0, true), weight(15.0, true)];
+        assert_eq!(
+            calculate_weight_probabilities(&spread, Weighting::Count).expect("probabilities"),
+            [50.0, 50.0]
+        );
+    }
+
+    #[test]
+    fn unique_picks_exhaust_without_repeats() {
+        let mut fortune = Fortune::builder()
+            .source(MemorySource::new("quotes", ["a", "b", "c"]), None)
+            .source(MemorySource::new("more", ["d", "e"]), None)
+            .seed(3)
+            .build()
+            .expect("build fortune");
+        let mut unique = fortune.unique();
+        let mut texts: Vec<String> = unique
+            .by_ref()
+            .map(|pick| pick.expect("pick").text)
+            .collect();
+        texts.sort();
+        assert_eq!(texts, ["a", "b", "c", "d", "e"]);
+        assert_eq!(unique.remaining(), 0);
+        unique.reset();
+        assert_eq!(unique.remaining(), 5);
+
+        assert_eq!(fortune.sample(Replacement::Without).take(10).count(), 5);
+        assert_eq!(fortune.sample(Replacement::With).take(10).count(), 10);
+    }
+
+    #[test]
+    fn unique_picks_see_through_tag_views() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("quotes");
+        let text = b"#tags: humor\nfunny\n%\nserious\n%\n#tags: humor\npun\n";
+        fs::write(&path, text).expect("write text");
+        let (dat, _) = build_dat_from_text(text, BuildOptions::default()).expect("build dat");
+        dat.write_to_path(&tmp.path().join("quotes.dat"))
+            .expect("write dat");
+
+        let mut fortune = Fortune::builder()
+            .sources([path.display().to_string(), "tag:humor".to_string()])
+            .seed(11)
+            .build()
+            .expect("build fortune");
+        let mut unique = fortune.unique();
+        assert_eq!(unique.remaining(), 3);
+        let mut texts: Vec<String> = unique
+            .by_ref()
+            .map(|pick| pick.expect("pick").text)
+            .collect();
+        texts.sort();
+        assert_eq!(
+            texts,
+            ["#tags: humor\nfunny\n", "#tags: humor\npun\n", "serious\n"]
+        );
+    }
+}
@@ -1,85 +1,407 @@
-use anyhow::{Result, bail};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, instrument, trace, warn};
 
-use crate::datfile::{FortuneFile, LengthFilter};
+use crate::auto_index::{AutoIndex, open_fortune_file};
+use crate::datfile::LengthFilter;
+use crate::error::{Error, Result};
+use crate::fortune_source::FortuneSource;
+use crate::layout::Layout;
+use crate::record::{RecordFilter, RecordMetadata};
 use crate::rng::FortuneRng;
 use crate::sources::WeightedSource;
+use crate::tag_index::TagIndex;
 
 #[derive(Debug, Clone)]
 pub struct LoadedSource {
-    pub db: FortuneFile,
+    pub source: Arc<dyn FortuneSource>,
     pub explicit_percent: Option<f64>,
+    pub relative_weight: Option<f64>,
+    pub merged_from: usize,
+    pub directory_percent: bool,
     pub candidate_indices: Vec<usize>,
+    pub tags: Option<Arc<TagIndex>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct FortuneSelection {
-    pub source_path: std::path::PathBuf,
+    pub source_name: String,
+    pub source_path: Option<PathBuf>,
     pub record_index: usize,
     pub text: String,
+    pub metadata: RecordMetadata,
 }
 
 #[derive(Debug, Clone)]
 pub struct MatchRecord {
-    pub source_path: std::path::PathBuf,
+    pub source_name: String,
+    pub source_path: Option<PathBuf>,
     pub record_index: usize,
     pub text: String,
+    pub metadata: RecordMetadata,
+}
+
+impl LoadedSource {
+    pub fn new(
+        source: Arc<dyn FortuneSource>,
+        explicit_percent: Option<f64>,
+        length_filter: LengthFilter,
+    ) -> Result<Self> {
+        let candidate_indices = source.candidate_indices(length_filter)?;
+        Ok(Self {
+            source,
+            explicit_percent,
+            relative_weight: None,
+            merged_from: 1,
+            directory_percent: false,
+            candidate_indices,
+            tags: None,
+        })
+    }
+
+    pub fn with_weight(mut self, relative_weight: Option<f64>) -> Self {
+        self.relative_weight = relative_weight;
+        self
+    }
+
+    pub fn candidate_bytes(&self) -> Result<u64> {
+        let mut total = 0u64;
+        for idx in &self.candidate_indices {
+            total += self.source.record_bytes(*idx)?.len() as u64;
+        }
+        Ok(total)
+    }
+
+    pub fn with_tag_index(mut self, tags: Option<TagIndex>) -> Self {
+        self.tags = tags.map(Arc::new);
+        self
+    }
+
+    pub fn retain_records(&mut self, filter: &RecordFilter) -> Result<()> {
+        if filter.is_empty() {
+            return Ok(());
+        }
+        let mut kept = Vec::with_capacity(self.candidate_indices.len());
+        if let Some(index) = self.tags.as_deref().filter(|_| filter.authors.is_empty()) {
+            let terms: Vec<(String, bool)> = filter.tag_terms().collect();
+            kept.extend(self.candidate_indices.iter().copied().filter(|idx| {
+                terms
+                    .iter()
+                    .all(|(tag, wanted)| index.records(tag).binary_search(idx).is_ok() == *wanted)
+            }));
+        } else {
+            for idx in &self.candidate_indices {
+                let text = self.source.record_text_lossy(*idx)?;
+                if filter.accepts(&RecordMetadata::parse(&text)) {
+                    kept.push(*idx);
+                }
+            }
+        }
+        trace!(
+            source = %self.source.display_name(),
+            before = self.candidate_indices.len(),
+            after = kept.len(),
+            "applied record filter"
+        );
+        self.candidate_indices = kept;
+        Ok(())
+    }
+
+    // Drops records that `--overflow skip` would never print, so selection
+    // only ever draws one that fits.
+    pub fn retain_fitting(&mut self, layout: &Layout, with_attribution: bool) -> Result<()> {
+        if !layout.skips_tall_records() {
+            return Ok(());
+        }
+        let before = self.candidate_indices.len();
+        let mut kept = Vec::with_capacity(before);
+        for idx in &self.candidate_indices {
+            let text = self.source.record_text_lossy(*idx)?;
+            let printable = RecordMetadata::parse(&text).printable(with_attribution);
+            if layout.apply(&printable).is_some() {
+                kept.push(*idx);
+            }
+        }
+        trace!(
+            source = %self.source.display_name(),
+            before,
+            after = kept.len(),
+            "dropped records taller than the layout"
+        );
+        self.candidate_indices = kept;
+        Ok(())
+    }
+
+    pub fn tagged_indices(&self, tag: &str) -> Result<Vec<usize>> {
+        let filter = RecordFilter {
+            tags: vec![tag.to_string()],
+            ..RecordFilter::default()
+        };
+        let mut view = self.clone();
+        view.retain_records(&filter)?;
+        Ok(view.candidate_indices)
+    }
+
+    pub fn tag_counts(&self) -> Result<BTreeMap<String, usize>> {
+        let mut counts = BTreeMap::new();
+        match self.tags.as_deref() {
+            Some(index) => {
+                for (tag, records) in &index.tags {
+                    let count = records
+                        .iter()
+                        .filter(|idx| self.candidate_indices.binary_search(idx).is_ok())
+                        .count();
+                    if count > 0 {
+                        counts.insert(tag.clone(), count);
+                    }
+                }
+            }
+            None => {
+                for idx in &self.candidate_indices {
+                    let meta = RecordMetadata::parse(&self.source.record_text_lossy(*idx)?);
+                    for tag in meta.tags {
+                        *counts.entry(tag).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        Ok(counts)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileSelectionMode {
     ProbabilityPercent,
+    WeightedDraw,
     CandidateCount,
 }
 
+// Resolution of the weighted draw; fine enough that shares well under 1%
+// keep their weight instead of rounding to whole percentage markers.
+const WEIGHTED_MARKERS: usize = 1_000_000_000;
+
+// Parity walks forward from a random record like upstream fortune, which
+// favors records that follow filtered-out runs; fair samples candidates
+// uniformly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecordSelection {
+    #[default]
+    Parity,
+    Fair,
+}
+
+impl FromStr for RecordSelection {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "parity" => Ok(Self::Parity),
+            "fair" => Ok(Self::Fair),
+            other => Err(Error::InvalidArgument(format!(
+                "unknown record selection '{other}' (expected parity|fair)"
+            ))),
+        }
+    }
+}
+
+// The RNG draws, marker walk and record skips behind one pick, as shown by
+// `--explain`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DrawTrace {
+    pub source_draw: usize,
+    pub source_range: usize,
+    pub walk: Vec<MarkerStep>,
+    pub chosen_source: usize,
+    pub record_selection: RecordSelection,
+    pub record_draw: usize,
+    pub record_total: usize,
+    pub initial_index: usize,
+    pub skipped_records: usize,
+    pub record_index: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MarkerStep {
+    pub source: usize,
+    pub marker: f64,
+    pub weight: f64,
+}
+
+// How sources without an explicit percentage share the remaining weight.
+// Relative weights (`3:linux`) take precedence over the strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Weighting {
+    #[default]
+    Count,
+    Equal,
+    Bytes,
+    Sqrt,
+}
+
+impl FromStr for Weighting {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "count" => Ok(Self::Count),
+            "equal" => Ok(Self::Equal),
+            "bytes" => Ok(Self::Bytes),
+            "sqrt" => Ok(Self::Sqrt),
+            other => Err(Error::InvalidArgument(format!(
+                "unknown weighting '{other}' (expected count|equal|bytes|sqrt)"
+            ))),
+        }
+    }
+}
+
+impl Weighting {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Count => "count",
+            Self::Equal => "equal",
+            Self::Bytes => "bytes",
+            Self::Sqrt => "sqrt",
+        }
+    }
+
+    fn base_weight(self, entry: &SourceWeight) -> f64 {
+        match self {
+            Self::Count => entry.candidate_count as f64,
+            Self::Equal => 1.0,
+            Self::Bytes => entry.candidate_bytes as f64,
+            Self::Sqrt => (entry.candidate_count as f64).sqrt(),
+        }
+    }
+}
+
+impl FileSelectionMode {
+    pub fn for_sources(entries: &[LoadedSource], weighting: Weighting) -> Self {
+        let weights: Vec<SourceWeight> = entries.iter().map(SourceWeight::from).collect();
+        Self::for_weights(&weights, weighting)
+    }
+
+    // Plain candidate counting keeps upstream's per-record draw, and `-e` or
+    // explicit percentages keep its integer percent walk. Weightings upstream
+    // never had draw over the exact shares instead.
+    pub fn for_weights(entries: &[SourceWeight], weighting: Weighting) -> Self {
+        if matches!(weighting, Weighting::Bytes | Weighting::Sqrt)
+            || entries.iter().any(|entry| entry.relative_weight.is_some())
+        {
+            Self::WeightedDraw
+        } else if weighting == Weighting::Equal
+            || entries.iter().any(|entry| entry.explicit_percent.is_some())
+        {
+            Self::ProbabilityPercent
+        } else {
+            Self::CandidateCount
+        }
+    }
+}
+
 #[instrument(skip_all)]
 pub fn load_sources(
     discovered: &[WeightedSource],
     length_filter: LengthFilter,
+    auto_index: AutoIndex,
 ) -> Result<Vec<LoadedSource>> {
     let mut out = Vec::new();
     for source in discovered {
-        let db = FortuneFile::open(&source.path)?;
-        let candidate_indices = db.candidate_indices(length_filter)?;
-        if candidate_indices.is_empty() {
-            trace!(path = %db.text_path.display(), "source has zero candidates under length filter");
+        let db = open_source(&source.path, auto_index)?;
+        let mut loaded = LoadedSource::new(db, source.explicit_percent, length_filter)?
+            .with_weight(source.relative_weight)
+            .with_tag_index(TagIndex::read_fresh(&source.path));
+        loaded.merged_from = source.merged_from;
+        loaded.directory_percent = source.directory_percent;
+        if loaded.candidate_indices.is_empty() {
+            trace!(path = %source.path.display(), "source has zero candidates under length filter");
             continue;
         }
-        out.push(LoadedSource {
-            db,
-            explicit_percent: source.explicit_percent,
-            candidate_indices,
-        });
+        out.push(loaded);
     }
     if out.is_empty() {
-        bail!("no fortune records are available after filtering");
+        return Err(Error::FilterLeftNothing);
     }
     debug!(loaded = out.len(), "loaded fortune sources");
     Ok(out)
 }
 
+pub fn open_source(path: &Path, auto_index: AutoIndex) -> Result<Arc<dyn FortuneSource>> {
+    #[cfg(feature = "builtin-corpus")]
+    if crate::discovery::is_builtin(path) {
+        return Ok(Arc::new(crate::builtin::BuiltinSource::load()?));
+    }
+    Ok(Arc::new(open_fortune_file(path, auto_index)?))
+}
+
 #[instrument(skip_all)]
-pub fn calculate_probabilities(entries: &[LoadedSource], equal_prob: bool) -> Result<Vec<f64>> {
+pub fn calculate_probabilities(entries: &[LoadedSource], weighting: Weighting) -> Result<Vec<f64>> {
+    let mut weights = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let mut weight = SourceWeight::from(entry);
+        // Byte totals need every candidate record, so only pay for them when asked.
+        if weighting == Weighting::Bytes {
+            weight.candidate_bytes = entry.candidate_bytes()?;
+        }
+        weights.push(weight);
+    }
+    calculate_weight_probabilities(&weights, weighting)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SourceWeight {
+    pub explicit_percent: Option<f64>,
+    pub relative_weight: Option<f64>,
+    pub candidate_count: usize,
+    pub candidate_bytes: u64,
+    pub directory_percent: bool,
+}
+
+impl From<&LoadedSource> for SourceWeight {
+    fn from(entry: &LoadedSource) -> Self {
+        Self {
+            explicit_percent: entry.explicit_percent,
+            relative_weight: entry.relative_weight,
+            candidate_count: entry.candidate_indices.len(),
+            candidate_bytes: 0,
+            directory_percent: entry.directory_percent,
+        }
+    }
+}
+
+pub fn calculate_weight_probabilities(
+    entries: &[SourceWeight],
+    weighting: Weighting,
+) -> Result<Vec<f64>> {
     if entries.is_empty() {
-        bail!("no source entries were provided");
+        return Err(Error::NoSources);
     }
 
     let specified_total: f64 = entries.iter().filter_map(|e| e.explicit_percent).sum();
     if specified_total > 100.0 + f64::EPSILON {
-        bail!("specified probability total exceeds 100%");
+        return Err(Error::PercentagesExceed100 {
+            total: specified_total,
+        });
     }
     let remaining = (100.0 - specified_total).max(0.0);
+    // Once any source has a relative weight, the others count as weight 1.
+    let relative = entries.iter().any(|entry| entry.relative_weight.is_some());
 
     let base_weights: Vec<f64> = entries
         .iter()
         .map(|entry| {
             if entry.explicit_percent.is_some() {
                 0.0
-            } else if equal_prob {
-                1.0
+            } else if relative {
+                entry.relative_weight.unwrap_or(1.0)
             } else {
-                entry.candidate_indices.len() as f64
+                weighting.base_weight(entry)
             }
         })
         .collect();
@@ -97,9 +419,24 @@ pub fn calculate_probabilities(entries: &[LoadedSource], equal_prob: bool) -> Re
         probs.push(prob.max(0.0));
     }
 
-    let sum: f64 = probs.iter().sum();
+    let mut sum: f64 = probs.iter().sum();
     if sum <= 0.0 {
-        bail!("computed source probabilities are all zero");
+        return Err(Error::ZeroProbability);
+    }
+    // When a directory or `all` percentage leaves a remainder nothing else
+    // can take (`30% all` on its own), scale the explicit shares up rather
+    // than handing it to whichever file the directory listed last. Plain
+    // file percentages keep upstream's fall-through to the last source.
+    let spread = entries.iter().any(|entry| entry.directory_percent);
+    if spread && total_base <= 0.0 && remaining > f64::EPSILON {
+        warn!(
+            specified_total,
+            "percentages leave a remainder with no source to take it; scaling to 100%"
+        );
+        for prob in &mut probs {
+            *prob *= 100.0 / sum;
+        }
+        sum = 100.0;
     }
     debug!(sum, count = probs.len(), "calculated source probabilities");
     Ok(probs)
@@ -111,79 +448,199 @@ pub fn select_random_fortune(
     probabilities: &[f64],
     rng: &mut FortuneRng,
     mode: FileSelectionMode,
+    records: RecordSelection,
 ) -> Result<FortuneSelection> {
+    select_random_fortune_with(entries, probabilities, rng, mode, records, None)
+}
+
+pub fn select_random_fortune_traced(
+    entries: &[LoadedSource],
+    probabilities: &[f64],
+    rng: &mut FortuneRng,
+    mode: FileSelectionMode,
+    records: RecordSelection,
+    trace: &mut DrawTrace,
+) -> Result<FortuneSelection> {
+    select_random_fortune_with(entries, probabilities, rng, mode, records, Some(trace))
+}
+
+fn select_random_fortune_with(
+    entries: &[LoadedSource],
+    probabilities: &[f64],
+    rng: &mut FortuneRng,
+    mode: FileSelectionMode,
+    records: RecordSelection,
+    mut trace: Option<&mut DrawTrace>,
+) -> Result<FortuneSelection> {
+    let weights: Vec<SourceWeight> = entries.iter().map(SourceWeight::from).collect();
+    let chosen_idx =
+        choose_source_index_with(&weights, probabilities, rng, mode, trace.as_deref_mut())?;
+    let chosen = &entries[chosen_idx];
+    select_record_with(
+        chosen.source.as_ref(),
+        &chosen.candidate_indices,
+        rng,
+        records,
+        trace,
+    )
+}
+
+pub fn choose_source_index(
+    entries: &[SourceWeight],
+    probabilities: &[f64],
+    rng: &mut FortuneRng,
+    mode: FileSelectionMode,
+) -> Result<usize> {
+    choose_source_index_with(entries, probabilities, rng, mode, None)
+}
+
+fn choose_source_index_with(
+    entries: &[SourceWeight],
+    probabilities: &[f64],
+    rng: &mut FortuneRng,
+    mode: FileSelectionMode,
+    trace: Option<&mut DrawTrace>,
+) -> Result<usize> {
     if entries.len() != probabilities.len() {
-        bail!("entries/probabilities length mismatch");
+        return Err(Error::InvalidArgument(
+            "entries/probabilities length mismatch".into(),
+        ));
     }
 
-    let chosen_idx = match mode {
-        FileSelectionMode::ProbabilityPercent => {
+    let mut walk = Vec::new();
+    let (draw, range, chosen_idx) = match mode {
+        FileSelectionMode::ProbabilityPercent | FileSelectionMode::WeightedDraw => {
             let total: f64 = probabilities.iter().sum();
             if total <= 0.0 {
-                bail!("total probability is zero");
+                return Err(Error::ZeroProbability);
             }
 
-            let mut marker = rng.next_index(100) as f64;
+            let (draw, range, mut marker) = if mode == FileSelectionMode::WeightedDraw {
+                let draw = rng.next_index(WEIGHTED_MARKERS);
+                let marker = draw as f64 / WEIGHTED_MARKERS as f64 * total;
+                (draw, WEIGHTED_MARKERS, marker)
+            } else {
+                let draw = rng.next_index(100);
+                (draw, 100, draw as f64)
+            };
             let mut picked = entries.len() - 1;
             for (idx, probability) in probabilities.iter().enumerate() {
+                walk.push(MarkerStep {
+                    source: idx,
+                    marker,
+                    weight: *probability,
+                });
                 if marker < *probability {
                     picked = idx;
                     break;
                 }
                 marker -= *probability;
             }
-            picked
+            (draw, range, picked)
         }
         FileSelectionMode::CandidateCount => {
-            let total_candidates: usize = entries
-                .iter()
-                .map(|entry| entry.candidate_indices.len())
-                .sum();
+            let total_candidates: usize = entries.iter().map(|entry| entry.candidate_count).sum();
             if total_candidates == 0 {
-                bail!("no candidate fortunes available");
+                return Err(Error::FilterLeftNothing);
             }
-            let mut marker = rng.next_index(total_candidates);
+            let draw = rng.next_index(total_candidates);
+            let mut marker = draw;
             let mut picked = entries.len() - 1;
             for (idx, entry) in entries.iter().enumerate() {
-                if marker < entry.candidate_indices.len() {
+                walk.push(MarkerStep {
+                    source: idx,
+                    marker: marker as f64,
+                    weight: entry.candidate_count as f64,
+                });
+                if marker < entry.candidate_count {
                     picked = idx;
                     break;
                 }
-                marker -= entry.candidate_indices.len();
+                marker -= entry.candidate_count;
             }
-            picked
+            (draw, total_candidates, picked)
         }
     };
+    if let Some(trace) = trace {
+        trace.source_draw = draw;
+        trace.source_range = range;
+        trace.walk = walk;
+        trace.chosen_source = chosen_idx;
+    }
+    Ok(chosen_idx)
+}
 
-    let chosen = &entries[chosen_idx];
-    let total_records = chosen.db.record_count();
+pub fn select_record(
+    source: &dyn FortuneSource,
+    candidate_indices: &[usize],
+    rng: &mut FortuneRng,
+    records: RecordSelection,
+) -> Result<FortuneSelection> {
+    select_record_with(source, candidate_indices, rng, records, None)
+}
+
+// `candidate_indices` must be sorted ascending.
+fn select_record_with(
+    source: &dyn FortuneSource,
+    candidate_indices: &[usize],
+    rng: &mut FortuneRng,
+    records: RecordSelection,
+    trace: Option<&mut DrawTrace>,
+) -> Result<FortuneSelection> {
+    let total_records = source.record_count();
     if total_records == 0 {
-        bail!("selected source has no records");
-    }
-    // Mirror upstream get_pos() behavior: first pick is incremented before use.
-    let mut record_index = (rng.next_index(total_records) + 1) % total_records;
-    if chosen.candidate_indices.len() != total_records {
-        let mut attempts = 0usize;
-        while !chosen.candidate_indices.contains(&record_index) {
-            record_index = (record_index + 1) % total_records;
-            attempts += 1;
-            if attempts >= total_records {
-                bail!(
-                    "selected source '{}' has no records matching filters",
-                    chosen.db.text_path.display()
-                );
+        return Err(Error::NoRecords {
+            path: source.display_name().into(),
+        });
+    }
+    debug_assert!(candidate_indices.is_sorted());
+    let (range, draw, initial_index, record_index, attempts) = match records {
+        RecordSelection::Parity => {
+            // Mirror upstream get_pos() behavior: first pick is incremented before use.
+            let draw = rng.next_index(total_records);
+            let initial_index = (draw + 1) % total_records;
+            let mut record_index = initial_index;
+            let mut attempts = 0usize;
+            if candidate_indices.len() != total_records {
+                while candidate_indices.binary_search(&record_index).is_err() {
+                    record_index = (record_index + 1) % total_records;
+                    attempts += 1;
+                    if attempts >= total_records {
+                        return Err(Error::NoMatchingRecords {
+                            path: source.display_name().into(),
+                        });
+                    }
+                }
+            }
+            (total_records, draw, initial_index, record_index, attempts)
+        }
+        RecordSelection::Fair => {
+            if candidate_indices.is_empty() {
+                return Err(Error::NoMatchingRecords {
+                    path: source.display_name().into(),
+                });
             }
+            let draw = rng.next_index(candidate_indices.len());
+            let record_index = candidate_indices[draw];
+            (candidate_indices.len(), draw, record_index, record_index, 0)
         }
+    };
+    if let Some(trace) = trace {
+        trace.record_selection = records;
+        trace.record_draw = draw;
+        trace.record_total = range;
+        trace.initial_index = initial_index;
+        trace.skipped_records = attempts;
+        trace.record_index = record_index;
     }
-    let text = chosen.db.record_text_lossy(record_index)?;
-    debug!(
-        source = %chosen.db.text_path.display(),
-        record_index,
-        "selected random fortune"
-    );
+    let text = source.record_text_lossy(record_index)?;
+    let source_name = source.display_name();
+    debug!(source = %source_name, record_index, "selected random fortune");
     Ok(FortuneSelection {
-        source_path: chosen.db.text_path.clone(),
+        source_path: source.metadata().path,
+        source_name,
         record_index,
+        metadata: RecordMetadata::parse(&text),
         text,
     })
 }
@@ -191,27 +648,28 @@ pub fn select_random_fortune(
 #[instrument(skip_all)]
 pub fn collect_matches(entries: &[LoadedSource], regex: &Regex) -> Result<Vec<MatchRecord>> {
     let mut out = Vec::new();
-    for source in entries {
+    for entry in entries {
+        let source_name = entry.source.display_name();
+        let source_path = entry.source.metadata().path;
         let mut source_matches = 0usize;
-        for record_index in &source.candidate_indices {
-            let text = source.db.record_text_lossy(*record_index)?;
+        for record_index in &entry.candidate_indices {
+            let text = entry.source.record_text_lossy(*record_index)?;
             if regex.is_match(&text) {
                 out.push(MatchRecord {
-                    source_path: source.db.text_path.clone(),
+                    source_name: source_name.clone(),
+                    source_path: source_path.clone(),
                     record_index: *record_index,
+                    metadata: RecordMetadata::parse(&text),
                     text,
                 });
                 source_matches += 1;
             }
         }
         if source_matches == 0 {
-            trace!(
-                source = %source.db.text_path.display(),
-                "source had no regex matches"
-            );
+            trace!(source = %source_name, "source had no regex matches");
         } else {
             warn!(
-                source = %source.db.text_path.display(),
+                source = %source_name,
                 matches = source_matches,
                 "source produced regex matches"
             );
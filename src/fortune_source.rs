@@ -0,0 +1,186 @@
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::datfile::{FortuneFile, LengthFilter};
+use crate::error::{Error, Result};
+
+pub trait FortuneSource: Debug + Send + Sync {
+    fn display_name(&self) -> String;
+
+    fn record_count(&self) -> usize;
+
+    fn record_bytes(&self, index: usize) -> Result<Cow<'_, [u8]>>;
+
+    fn metadata(&self) -> SourceMetadata;
+
+    // The source and index a record is stored under when this source is a
+    // view over others, like `tag:`; `None` means the record is its own.
+    fn origin(&self, _index: usize) -> Option<(&dyn FortuneSource, usize)> {
+        None
+    }
+
+    fn record_text_lossy(&self, index: usize) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.record_bytes(index)?).into_owned())
+    }
+
+    fn candidate_indices(&self, filter: LengthFilter) -> Result<Vec<usize>> {
+        let mut out = Vec::new();
+        for idx in 0..self.record_count() {
+            if filter.accepts(self.record_bytes(idx)?.len()) {
+                out.push(idx);
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SourceKind {
+    TextDat,
+    Memory,
+    Json,
+    Builtin,
+    Tagged,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMetadata {
+    pub kind: SourceKind,
+    pub path: Option<PathBuf>,
+    pub offensive: bool,
+}
+
+impl FortuneSource for FortuneFile {
+    fn display_name(&self) -> String {
+        self.text_path.display().to_string()
+    }
+
+    fn record_count(&self) -> usize {
+        FortuneFile::record_count(self)
+    }
+
+    fn record_bytes(&self, index: usize) -> Result<Cow<'_, [u8]>> {
+        FortuneFile::record_bytes(self, index).map(Cow::Borrowed)
+    }
+
+    fn metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            kind: SourceKind::TextDat,
+            path: Some(self.text_path.clone()),
+            offensive: self
+                .text_path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .is_some_and(|s| s.ends_with("-o")),
+        }
+    }
+
+    fn candidate_indices(&self, filter: LengthFilter) -> Result<Vec<usize>> {
+        FortuneFile::candidate_indices(self, filter)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MemorySource {
+    name: String,
+    records: Vec<Vec<u8>>,
+    kind: SourceKind,
+    path: Option<PathBuf>,
+    offensive: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum JsonRecord {
+    Text(String),
+    Object { text: String },
+}
+
+impl MemorySource {
+    pub fn new<I, R>(name: impl Into<String>, records: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: Into<Vec<u8>>,
+    {
+        Self {
+            name: name.into(),
+            records: records.into_iter().map(Into::into).collect(),
+            kind: SourceKind::Memory,
+            path: None,
+            offensive: false,
+        }
+    }
+
+    pub fn from_json_file(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).map_err(Error::io("reading", path))?;
+        let records: Vec<JsonRecord> = serde_json::from_slice(&bytes).map_err(|err| {
+            Error::InvalidArgument(format!("invalid JSON corpus {}: {err}", path.display()))
+        })?;
+        let texts = records.into_iter().map(|record| match record {
+            JsonRecord::Text(text) | JsonRecord::Object { text } => text,
+        });
+        Ok(Self {
+            kind: SourceKind::Json,
+            path: Some(path.to_path_buf()),
+            ..Self::new(path.display().to_string(), texts)
+        })
+    }
+
+    pub fn offensive(mut self, offensive: bool) -> Self {
+        self.offensive = offensive;
+        self
+    }
+}
+
+impl FortuneSource for MemorySource {
+    fn display_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn record_count(&self) -> usize {
+        self.records.len()
+    }
+
+    fn record_bytes(&self, index: usize) -> Result<Cow<'_, [u8]>> {
+        self.records
+            .get(index)
+            .map(|record| Cow::Borrowed(record.as_slice()))
+            .ok_or(Error::RecordOutOfRange { index })
+    }
+
+    fn metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            kind: self.kind,
+            path: self.path.clone(),
+            offensive: self.offensive,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_corpus_accepts_strings_and_objects() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("quotes.json");
+        fs::write(&path, br#"["plain", {"text": "object", "author": "x"}]"#).expect("write");
+
+        let source = MemorySource::from_json_file(&path).expect("load json");
+        assert_eq!(source.record_count(), 2);
+        assert_eq!(source.record_text_lossy(1).expect("record"), "object");
+        assert_eq!(source.metadata().kind, SourceKind::Json);
+        assert_eq!(
+            source
+                .candidate_indices(LengthFilter::Short { threshold: 5 })
+                .expect("filter"),
+            vec![0]
+        );
+    }
+}
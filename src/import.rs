@@ -0,0 +1,279 @@
+use std::fmt::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::{debug, instrument, warn};
+
+use crate::error::{Error, Result};
+
+// Keys API dumps usually wrap their list in, e.g. {"count": 2, "results": [...]}.
+const LIST_KEYS: [&str; 4] = ["quotes", "results", "data", "items"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Json,
+    Csv,
+    Yaml,
+    Lines,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ImportedQuote {
+    #[serde(alias = "quote", alias = "content", alias = "body")]
+    pub text: String,
+    #[serde(default, alias = "by")]
+    pub author: Option<String>,
+    #[serde(default, alias = "work", alias = "book")]
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawQuote {
+    Text(String),
+    Quote(ImportedQuote),
+}
+
+impl ImportFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "txt" => Some(Self::Lines),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for ImportFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            "lines" => Ok(Self::Lines),
+            other => Err(Error::InvalidArgument(format!(
+                "unknown import format '{other}' (expected json|csv|yaml|lines)"
+            ))),
+        }
+    }
+}
+
+impl ImportedQuote {
+    pub fn render(&self) -> String {
+        let mut out = self.text.trim_end().to_string();
+        let author = non_empty(self.author.as_deref());
+        let source = non_empty(self.source.as_deref());
+        let attribution = match (author, source) {
+            (Some(author), Some(source)) => Some(format!("{author}, {source}")),
+            (Some(only), None) | (None, Some(only)) => Some(only.to_string()),
+            (None, None) => None,
+        };
+        if let Some(attribution) = attribution {
+            let _ = write!(out, "\n\t\t-- {attribution}");
+        }
+        out.push('\n');
+        out
+    }
+}
+
+#[instrument(skip_all, fields(path = %path.display(), ?format))]
+pub fn parse_quotes(
+    path: &Path,
+    bytes: &[u8],
+    format: ImportFormat,
+    field: Option<&str>,
+) -> Result<Vec<ImportedQuote>> {
+    let invalid = |reason: String| Error::InvalidImport {
+        path: path.to_path_buf(),
+        reason,
+    };
+    let quotes = match format {
+        ImportFormat::Json => {
+            let doc: Value =
+                serde_json::from_slice(bytes).map_err(|err| invalid(err.to_string()))?;
+            quotes_from_value(doc, field).map_err(invalid)?
+        }
+        ImportFormat::Yaml => {
+            let doc: Value =
+                serde_yaml::from_slice(bytes).map_err(|err| invalid(err.to_string()))?;
+            quotes_from_value(doc, field).map_err(invalid)?
+        }
+        ImportFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(bytes);
+            let mut out = Vec::new();
+            for row in reader.deserialize() {
+                out.push(row.map_err(|err: csv::Error| invalid(err.to_string()))?);
+            }
+            out
+        }
+        ImportFormat::Lines => String::from_utf8_lossy(bytes)
+            .lines()
+            .map(|line| ImportedQuote {
+                text: line.to_string(),
+                author: None,
+                source: None,
+            })
+            .collect(),
+    };
+
+    let mut kept = Vec::with_capacity(quotes.len());
+    for (idx, quote) in quotes.into_iter().enumerate() {
+        if quote.text.trim().is_empty() {
+            if format != ImportFormat::Lines {
+                warn!(record = idx, "skipping quote with empty text");
+            }
+            continue;
+        }
+        kept.push(quote);
+    }
+    debug!(quotes = kept.len(), "parsed import source");
+    Ok(kept)
+}
+
+pub fn render_records(path: &Path, quotes: &[ImportedQuote], delimiter: u8) -> Result<Vec<String>> {
+    let mut records = Vec::with_capacity(quotes.len());
+    for (idx, quote) in quotes.iter().enumerate() {
+        let record = quote.render();
+        if record
+            .lines()
+            .any(|line| line.trim_end_matches('\r').as_bytes() == [delimiter])
+        {
+            return Err(Error::InvalidImport {
+                path: path.to_path_buf(),
+                reason: format!(
+                    "quote {} contains a line consisting only of the delimiter '{}'",
+                    idx + 1,
+                    delimiter as char
+                ),
+            });
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn quotes_from_value(
+    doc: Value,
+    field: Option<&str>,
+) -> std::result::Result<Vec<ImportedQuote>, String> {
+    let items = match (doc, field) {
+        (Value::Array(items), None) => items,
+        (Value::Object(fields), field) => quote_list(fields, field)?,
+        (_, Some(field)) => return Err(format!("expected an object with a '{field}' field")),
+        (_, None) => return Err("expected a list of quotes".into()),
+    };
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(idx, item)| match serde_json::from_value(item) {
+            Ok(RawQuote::Text(text)) => Ok(ImportedQuote {
+                text,
+                author: None,
+                source: None,
+            }),
+            Ok(RawQuote::Quote(quote)) => Ok(quote),
+            Err(_) => Err(format!(
+                "quote {} is neither a string nor an object with a text field",
+                idx + 1
+            )),
+        })
+        .collect()
+}
+
+// Picks the list of quotes out of a wrapping object: the named field, else
+// a well-known key, else the only non-empty list of strings or objects.
+fn quote_list(
+    mut fields: serde_json::Map<String, Value>,
+    field: Option<&str>,
+) -> std::result::Result<Vec<Value>, String> {
+    if let Some(field) = field {
+        return match fields.remove(field) {
+            Some(Value::Array(items)) => Ok(items),
+            Some(_) => Err(format!("field '{field}' is not a list")),
+            None => Err(format!("object has no field '{field}'")),
+        };
+    }
+    let known = LIST_KEYS
+        .into_iter()
+        .find(|key| matches!(fields.get(*key), Some(Value::Array(_))));
+    if let Some(Value::Array(items)) = known.and_then(|key| fields.remove(key)) {
+        return Ok(items);
+    }
+    let candidates: Vec<String> = fields
+        .iter()
+        .filter(|(_, value)| match value {
+            Value::Array(items) => {
+                !items.is_empty()
+                    && items
+                        .iter()
+                        .all(|item| item.is_string() || item.is_object())
+            }
+            _ => false,
+        })
+        .map(|(key, _)| key.clone())
+        .collect();
+    if let [key] = candidates.as_slice()
+        && let Some(Value::Array(items)) = fields.remove(key)
+    {
+        return Ok(items);
+    }
+    if candidates.is_empty() {
+        return Err("object contains no list of quotes".into());
+    }
+    Err(format!(
+        "object has several lists of quotes ({}); choose one with --field",
+        candidates.join(", ")
+    ))
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapped_json_and_csv_map_attribution() {
+        let path = Path::new("quotes");
+        let json = br#"{"count": 2, "results": ["plain", {"quote": "Be brief.", "author": "Anon", "work": "Notes"}]}"#;
+        let quotes = parse_quotes(path, json, ImportFormat::Json, None).expect("json");
+        assert_eq!(quotes[0].render(), "plain\n");
+        assert_eq!(quotes[1].render(), "Be brief.\n\t\t-- Anon, Notes\n");
+
+        let csv = b"id,text,author\n1,\"Two\nlines\",Someone\n2,,Nobody\n3,Solo,\n";
+        let quotes = parse_quotes(path, csv, ImportFormat::Csv, None).expect("csv");
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[0].render(), "Two\nlines\n\t\t-- Someone\n");
+        assert_eq!(quotes[1].render(), "Solo\n");
+    }
+
+    #[test]
+    fn wrapped_json_picks_the_list_of_quotes() {
+        let path = Path::new("quotes");
+        let parse = |json: &[u8], field| parse_quotes(path, json, ImportFormat::Json, field);
+
+        let api = br#"{"errors": [], "results": [{"text": "a quote", "author": "X"}]}"#;
+        let quotes = parse(api, None).expect("known key");
+        assert_eq!(quotes[0].render(), "a quote\n\t\t-- X\n");
+
+        let only = br#"{"meta": {"page": 1}, "tags": [1, 2], "entries": ["one"]}"#;
+        assert_eq!(parse(only, None).expect("only list")[0].text, "one");
+
+        let ambiguous = br#"{"drafts": ["draft"], "entries": ["one"]}"#;
+        let err = parse(ambiguous, None).expect_err("ambiguous").to_string();
+        assert!(err.contains("(drafts, entries)"), "{err}");
+        assert_eq!(
+            parse(ambiguous, Some("drafts")).expect("field")[0].text,
+            "draft"
+        );
+        assert!(parse(ambiguous, Some("missing")).is_err());
+    }
+}
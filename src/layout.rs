@@ -0,0 +1,250 @@
+use std::env;
+use std::str::FromStr;
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::error::{Error, Result};
+
+const DEFAULT_COLUMNS: usize = 80;
+const TAB_WIDTH: usize = 8;
+const ELLIPSIS: &str = " …";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    #[default]
+    Truncate,
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Layout {
+    pub wrap: Option<usize>,
+    pub max_lines: Option<usize>,
+    pub overflow: Overflow,
+}
+
+impl FromStr for Overflow {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "truncate" => Ok(Self::Truncate),
+            "skip" => Ok(Self::Skip),
+            other => Err(Error::InvalidArgument(format!(
+                "unknown overflow mode '{other}' (expected truncate|skip)"
+            ))),
+        }
+    }
+}
+
+impl Layout {
+    pub fn is_identity(&self) -> bool {
+        self.wrap.is_none() && self.max_lines.is_none()
+    }
+
+    // Whether records taller than `max_lines` are dropped rather than cut.
+    pub fn skips_tall_records(&self) -> bool {
+        self.overflow == Overflow::Skip && self.max_lines.is_some()
+    }
+
+    pub fn apply(&self, text: &str) -> Option<String> {
+        let wrapped = match self.wrap {
+            Some(width) => wrap_text(text, width),
+            None => text.to_string(),
+        };
+        let Some(max_lines) = self.max_lines else {
+            return Some(wrapped);
+        };
+        if wrapped.lines().count() <= max_lines {
+            return Some(wrapped);
+        }
+        match self.overflow {
+            Overflow::Skip => None,
+            Overflow::Truncate => {
+                let mut kept: Vec<&str> = wrapped.lines().take(max_lines).collect();
+                let last = kept.pop().unwrap_or_default().trim_end();
+                let mut out = kept.join("\n");
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                out.push_str(last);
+                out.push_str(if last.is_empty() { "…" } else { ELLIPSIS });
+                out.push('\n');
+                Some(out)
+            }
+        }
+    }
+}
+
+pub fn terminal_columns() -> usize {
+    if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
+        return usize::from(width);
+    }
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|cols| cols.trim().parse().ok())
+        .filter(|cols| *cols > 0)
+        .unwrap_or(DEFAULT_COLUMNS)
+}
+
+pub fn display_width(text: &str) -> usize {
+    text.chars().fold(0, |col, ch| {
+        if ch == '\t' {
+            (col / TAB_WIDTH + 1) * TAB_WIDTH
+        } else {
+            col + ch.width().unwrap_or(0)
+        }
+    })
+}
+
+pub fn expand_tabs(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut col = 0;
+    for ch in line.chars() {
+        if ch == '\t' {
+            let next = (col / TAB_WIDTH + 1) * TAB_WIDTH;
+            out.extend(std::iter::repeat_n(' ', next - col));
+            col = next;
+        } else {
+            out.push(ch);
+            col += ch.width().unwrap_or(0);
+        }
+    }
+    out
+}
+
+pub fn wrap_text(text: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut out = String::with_capacity(text.len() + text.len() / width);
+    for line in text.split_inclusive('\n') {
+        let (content, newline) = match line.strip_suffix('\n') {
+            Some(content) => (content, "\n"),
+            None => (line, ""),
+        };
+        if display_width(content) <= width || looks_like_art(content) {
+            out.push_str(line);
+            continue;
+        }
+        let body = content.trim_start();
+        // Indentation deeper than half the width would leave only slivers of
+        // text, so such lines are wrapped from column 0 instead.
+        let indent = Some(&content[..content.len() - body.len()])
+            .filter(|indent| display_width(indent) * 2 <= width)
+            .unwrap_or_default();
+        // Attribution lines hang under the author rather than the dashes.
+        let continuation = match body.strip_prefix("-- ") {
+            Some(_) => format!("{indent}   "),
+            None => indent.to_string(),
+        };
+        let continuation = if display_width(&continuation) * 2 <= width {
+            continuation
+        } else {
+            indent.to_string()
+        };
+        wrap_line(&mut out, indent, &continuation, body, width);
+        out.push_str(newline);
+    }
+    out
+}
+
+fn wrap_line(out: &mut String, indent: &str, continuation: &str, body: &str, width: usize) {
+    let mut prefix = indent;
+    let mut line = String::new();
+    let mut line_width = 0;
+    let mut start_width = 0;
+    let mut line_has_word = false;
+    for word in body.split_whitespace() {
+        let word_width = display_width(word);
+        if line_has_word && line_width + 1 + word_width > width {
+            out.push_str(line.trim_end());
+            out.push('\n');
+            prefix = continuation;
+            line_has_word = false;
+        }
+        if line_has_word {
+            line.push(' ');
+            line_width += 1;
+        } else {
+            // A word that fits the width is never split just to keep the indent.
+            let keep_prefix = display_width(prefix) + word_width <= width || word_width > width;
+            line = if keep_prefix { prefix } else { "" }.to_string();
+            line_width = display_width(&line);
+            start_width = line_width;
+        }
+        for ch in word.chars() {
+            let ch_width = ch.width().unwrap_or(0);
+            if line_width + ch_width > width && line_width > start_width {
+                out.push_str(&line);
+                out.push('\n');
+                line = continuation.to_string();
+                line_width = display_width(continuation);
+                start_width = line_width;
+            }
+            line.push(ch);
+            line_width += ch_width;
+        }
+        line_has_word = true;
+    }
+    out.push_str(line.trim_end());
+}
+
+fn looks_like_art(line: &str) -> bool {
+    let body = line.trim();
+    if body.contains("   ") {
+        return true;
+    }
+    let visible = body.chars().filter(|ch| !ch.is_whitespace()).count();
+    let symbols = body
+        .chars()
+        .filter(|ch| !ch.is_whitespace() && !ch.is_alphanumeric())
+        .count();
+    visible >= 4 && symbols * 5 >= visible * 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_preserves_breaks_indentation_and_art() {
+        let text = "Short line.\n  An indented line that is long enough to need wrapping.\n(\\_/)   (o.o)   art stays put on its own line\n\t\t-- Somebody Famous, A Very Long Book Title\n";
+        assert_eq!(
+            wrap_text(text, 40),
+            "Short line.\n  An indented line that is long enough\n  to need wrapping.\n(\\_/)   (o.o)   art stays put on its own line\n\t\t-- Somebody Famous, A\n\t\t   Very Long Book Title\n"
+        );
+        assert_eq!(wrap_text("日本語の文章です", 6), "日本語\nの文章\nです");
+    }
+
+    #[test]
+    fn wrap_never_shreds_deeply_indented_attributions() {
+        let text = "Short.\n\t\t-- Ambrose Bierce, The Devil's Dictionary\n";
+        assert_eq!(
+            wrap_text(text, 22),
+            "Short.\n-- Ambrose Bierce, The\n   Devil's Dictionary\n"
+        );
+        assert_eq!(
+            wrap_text("    supercalifragilistic word", 24),
+            "    supercalifragilistic\n    word"
+        );
+        assert_eq!(
+            wrap_text("  a supercalifragilisticexp", 24),
+            "  a\nsupercalifragilisticexp"
+        );
+    }
+
+    #[test]
+    fn max_lines_truncates_or_skips() {
+        let text = "one\ntwo\nthree\n";
+        let truncate = Layout {
+            max_lines: Some(2),
+            ..Layout::default()
+        };
+        assert_eq!(truncate.apply(text).as_deref(), Some("one\ntwo …\n"));
+        let skip = Layout {
+            overflow: Overflow::Skip,
+            ..truncate
+        };
+        assert_eq!(skip.apply(text), None);
+        assert_eq!(skip.apply("one\n").as_deref(), Some("one\n"));
+    }
+}
@@ -1,7 +1,32 @@
+pub mod auto_index;
+#[cfg(feature = "builtin-corpus")]
+pub mod builtin;
+pub mod config;
+pub mod corpus;
 pub mod datfile;
+pub mod dedupe;
 pub mod discovery;
+pub mod discovery_cache;
+pub mod error;
+pub mod explain;
+pub mod export;
+pub mod fortune;
 pub mod fortune_engine;
+pub mod fortune_source;
+pub mod hash;
+pub mod import;
+pub mod layout;
+pub mod lint;
 pub mod logging;
+pub mod pacing;
+pub mod record;
+pub mod render;
 pub mod rng;
+pub mod schedule;
+pub mod simulate;
 pub mod sources;
 pub mod strfile_builder;
+pub mod tag_index;
+
+pub use error::{Error, Result};
+pub use fortune::{Fortune, FortuneBuilder};
@@ -0,0 +1,464 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use tracing::{debug, instrument};
+
+use crate::strfile_builder::parse_record_spans;
+
+const TAB_WIDTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LintConfig {
+    pub delimiter: u8,
+    pub max_columns: usize,
+    pub max_record_len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    EmptyRecord,
+    TrailingWhitespace,
+    MixedLineEndings,
+    Tab,
+    LongLine,
+    InvalidUtf8,
+    NearDuplicate,
+    LongRecord,
+    MissingFinalNewline,
+    DelimiterLike,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: LintKind,
+    pub line: usize,
+    pub column: usize,
+    pub width: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Line<'a> {
+    number: usize,
+    start: usize,
+    content: &'a [u8],
+    crlf: bool,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            delimiter: b'%',
+            max_columns: 80,
+            max_record_len: 2000,
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+impl LintKind {
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::EmptyRecord => "empty-record",
+            Self::TrailingWhitespace => "trailing-whitespace",
+            Self::MixedLineEndings => "mixed-line-endings",
+            Self::Tab => "tab",
+            Self::LongLine => "long-line",
+            Self::InvalidUtf8 => "invalid-utf8",
+            Self::NearDuplicate => "near-duplicate",
+            Self::LongRecord => "long-record",
+            Self::MissingFinalNewline => "missing-final-newline",
+            Self::DelimiterLike => "delimiter-like",
+        }
+    }
+
+    pub fn severity(self) -> Severity {
+        match self {
+            Self::InvalidUtf8 | Self::DelimiterLike => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+
+    pub fn fixable(self) -> bool {
+        matches!(
+            self,
+            Self::EmptyRecord
+                | Self::TrailingWhitespace
+                | Self::MixedLineEndings
+                | Self::MissingFinalNewline
+        )
+    }
+}
+
+#[instrument(skip_all, fields(bytes = input.len()))]
+pub fn lint_corpus(input: &[u8], config: &LintConfig) -> Vec<Diagnostic> {
+    let lines = split_lines(input);
+    let mut out = Vec::new();
+
+    let crlf_count = lines.iter().filter(|line| line.crlf).count();
+    let terminated = terminated_lines(input, &lines);
+    if crlf_count > 0 && crlf_count < terminated {
+        let minority_crlf = crlf_count * 2 <= terminated;
+        if let Some(line) = lines.iter().find(|line| line.crlf == minority_crlf) {
+            out.push(Diagnostic {
+                kind: LintKind::MixedLineEndings,
+                line: line.number,
+                column: line.content.len() + 1,
+                width: 1,
+                message: format!(
+                    "file mixes CRLF and LF line endings ({crlf_count} of {terminated} lines use CRLF)"
+                ),
+            });
+        }
+    }
+
+    for line in &lines {
+        lint_line(line, config, &mut out);
+    }
+
+    let spans = parse_record_spans(input, config.delimiter, true);
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for (idx, span) in spans.iter().enumerate() {
+        let record = &input[span.start..span.end];
+        let line = line_number_at(&lines, span.start);
+        if record.iter().all(u8::is_ascii_whitespace) {
+            // A trailing empty span after the last delimiter is just the end of the file.
+            if idx + 1 == spans.len() && idx > 0 && record.is_empty() {
+                continue;
+            }
+            out.push(Diagnostic {
+                kind: LintKind::EmptyRecord,
+                line,
+                column: 1,
+                width: 1,
+                message: format!("record {} is empty", idx + 1),
+            });
+            continue;
+        }
+        if record.len() > config.max_record_len {
+            out.push(Diagnostic {
+                kind: LintKind::LongRecord,
+                line,
+                column: 1,
+                width: 1,
+                message: format!(
+                    "record {} is {} bytes, over the {} byte limit",
+                    idx + 1,
+                    record.len(),
+                    config.max_record_len
+                ),
+            });
+        }
+        let key = normalize_for_duplicates(record);
+        if let Some(first) = seen.get(&key) {
+            out.push(Diagnostic {
+                kind: LintKind::NearDuplicate,
+                line,
+                column: 1,
+                width: 1,
+                message: format!("record {} duplicates record {first}", idx + 1),
+            });
+        } else {
+            seen.insert(key, idx + 1);
+        }
+    }
+
+    if !input.is_empty()
+        && !input.ends_with(b"\n")
+        && let Some(last) = lines.last()
+    {
+        out.push(Diagnostic {
+            kind: LintKind::MissingFinalNewline,
+            line: last.number,
+            column: last.content.len() + 1,
+            width: 1,
+            message: "file does not end with a newline".into(),
+        });
+    }
+
+    out.sort_by_key(|diag| (diag.line, diag.column));
+    debug!(diagnostics = out.len(), "linted corpus");
+    out
+}
+
+// Applies only the fixable rewrites that `lint_corpus` reports, so a clean
+// file comes back byte for byte.
+#[instrument(skip_all, fields(bytes = input.len()))]
+pub fn fix_corpus(input: &[u8], config: &LintConfig) -> Vec<u8> {
+    let diagnostics: Vec<Diagnostic> = lint_corpus(input, config)
+        .into_iter()
+        .filter(|diag| diag.kind.fixable())
+        .collect();
+    if diagnostics.is_empty() {
+        return input.to_vec();
+    }
+    let fixes = |kind: LintKind| diagnostics.iter().any(|diag| diag.kind == kind);
+    let trims = |number: usize| {
+        diagnostics
+            .iter()
+            .any(|diag| diag.kind == LintKind::TrailingWhitespace && diag.line == number)
+    };
+
+    let lines = split_lines(input);
+    let crlf_count = lines.iter().filter(|line| line.crlf).count();
+    let terminated = terminated_lines(input, &lines);
+    let file_eol: &[u8] = if crlf_count > 0 && crlf_count == terminated {
+        b"\r\n"
+    } else {
+        b"\n"
+    };
+
+    let mut normalized = Vec::with_capacity(input.len());
+    for (idx, line) in lines.iter().enumerate() {
+        if trims(line.number) {
+            normalized.extend_from_slice(trim_trailing_whitespace(line.content));
+        } else {
+            normalized.extend_from_slice(line.content);
+        }
+        let has_eol = idx < terminated;
+        let eol: &[u8] = if !has_eol || fixes(LintKind::MixedLineEndings) {
+            file_eol
+        } else if line.crlf {
+            b"\r\n"
+        } else {
+            b"\n"
+        };
+        if has_eol || fixes(LintKind::MissingFinalNewline) {
+            normalized.extend_from_slice(eol);
+        }
+    }
+    if !fixes(LintKind::EmptyRecord) {
+        return normalized;
+    }
+
+    // Drop each empty record together with one neighbouring delimiter line,
+    // keeping the conventional delimiter after the last record.
+    let spans = parse_record_spans(&normalized, config.delimiter, true);
+    let mut out = Vec::with_capacity(normalized.len());
+    let mut kept_from = 0;
+    for (idx, span) in spans.iter().enumerate() {
+        let record = &normalized[span.start..span.end];
+        let is_last = idx + 1 == spans.len();
+        if !record.iter().all(u8::is_ascii_whitespace) || (is_last && idx > 0 && record.is_empty())
+        {
+            continue;
+        }
+        let (drop_start, drop_end) = match spans.get(idx + 1) {
+            Some(next) => (span.start, next.start),
+            None if idx > 0 => (spans[idx - 1].end, span.end),
+            None => (span.start, span.end),
+        };
+        out.extend_from_slice(&normalized[kept_from..drop_start.max(kept_from)]);
+        kept_from = drop_end;
+    }
+    out.extend_from_slice(&normalized[kept_from..]);
+    out
+}
+
+pub fn source_line(input: &[u8], line: usize) -> String {
+    let lines = split_lines(input);
+    lines
+        .get(line.saturating_sub(1))
+        .map(|line| String::from_utf8_lossy(line.content).into_owned())
+        .unwrap_or_default()
+}
+
+fn lint_line(line: &Line<'_>, config: &LintConfig, out: &mut Vec<Diagnostic>) {
+    let content = line.content;
+    let is_delimiter = content == [config.delimiter];
+    if is_delimiter_like(content, config.delimiter) {
+        out.push(Diagnostic {
+            kind: LintKind::DelimiterLike,
+            line: line.number,
+            column: 1,
+            width: content.len(),
+            message: format!(
+                "line looks like a '{}' delimiter but has extra whitespace, so it is part of the record",
+                config.delimiter as char
+            ),
+        });
+        return;
+    }
+    if is_delimiter {
+        return;
+    }
+
+    let text = match std::str::from_utf8(content) {
+        Ok(text) => text,
+        Err(err) => {
+            let start = err.valid_up_to();
+            out.push(Diagnostic {
+                kind: LintKind::InvalidUtf8,
+                line: line.number,
+                column: column_at(&String::from_utf8_lossy(&content[..start])) + 1,
+                width: err.error_len().unwrap_or(1),
+                message: format!("invalid UTF-8 at byte {}", line.start + start),
+            });
+            return;
+        }
+    };
+
+    let trimmed = trim_trailing_whitespace(content).len();
+    if trimmed < content.len() {
+        out.push(Diagnostic {
+            kind: LintKind::TrailingWhitespace,
+            line: line.number,
+            column: column_at(&text[..trimmed]) + 1,
+            width: content.len() - trimmed,
+            message: "trailing whitespace".into(),
+        });
+    }
+
+    let indent = text.len() - text.trim_start_matches('\t').len();
+    if let Some(pos) = text[indent..trimmed.max(indent)].find('\t') {
+        out.push(Diagnostic {
+            kind: LintKind::Tab,
+            line: line.number,
+            column: column_at(&text[..indent + pos]) + 1,
+            width: 1,
+            message: "tab character after text renders differently across terminals".into(),
+        });
+    }
+
+    let columns = column_at(&text[..trimmed]);
+    if columns > config.max_columns {
+        out.push(Diagnostic {
+            kind: LintKind::LongLine,
+            line: line.number,
+            column: config.max_columns + 1,
+            width: columns - config.max_columns,
+            message: format!(
+                "line is {columns} columns, over the {} column limit",
+                config.max_columns
+            ),
+        });
+    }
+}
+
+fn split_lines(input: &[u8]) -> Vec<Line<'_>> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    while start < input.len() {
+        let end = input[start..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map(|rel| start + rel);
+        let line_end = end.unwrap_or(input.len());
+        let crlf = end.is_some() && line_end > start && input[line_end - 1] == b'\r';
+        let content_end = if crlf { line_end - 1 } else { line_end };
+        out.push(Line {
+            number: out.len() + 1,
+            start,
+            content: &input[start..content_end],
+            crlf,
+        });
+        start = line_end + 1;
+    }
+    out
+}
+
+fn terminated_lines(input: &[u8], lines: &[Line<'_>]) -> usize {
+    lines.len() - usize::from(!input.ends_with(b"\n") && !input.is_empty())
+}
+
+fn line_number_at(lines: &[Line<'_>], offset: usize) -> usize {
+    match lines.binary_search_by_key(&offset, |line| line.start) {
+        Ok(idx) => idx + 1,
+        Err(idx) => idx.max(1),
+    }
+}
+
+fn is_delimiter_like(content: &[u8], delimiter: u8) -> bool {
+    content != [delimiter] && content.len() > 1 && content.trim_ascii() == [delimiter]
+}
+
+fn trim_trailing_whitespace(content: &[u8]) -> &[u8] {
+    content.trim_ascii_end()
+}
+
+fn column_at(text: &str) -> usize {
+    text.chars().fold(0, |col, ch| {
+        if ch == '\t' {
+            (col / TAB_WIDTH + 1) * TAB_WIDTH
+        } else {
+            col + 1
+        }
+    })
+}
+
+fn normalize_for_duplicates(record: &[u8]) -> String {
+    String::from_utf8_lossy(record)
+        .chars()
+        .filter(|ch| ch.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &[u8]) -> Vec<(LintKind, usize)> {
+        lint_corpus(input, &LintConfig::default())
+            .into_iter()
+            .map(|diag| (diag.kind, diag.line))
+            .collect()
+    }
+
+    #[test]
+    fn flags_common_corpus_problems() {
+        let input =
+            b"One two.  \n%\n%\nOne, two!\n%\n% \nx\tlate\n\t\t-- Author\n%\n\xff\r\n%\nend";
+        assert_eq!(
+            kinds(input),
+            vec![
+                (LintKind::TrailingWhitespace, 1),
+                (LintKind::EmptyRecord, 3),
+                (LintKind::NearDuplicate, 4),
+                (LintKind::DelimiterLike, 6),
+                (LintKind::Tab, 7),
+                (LintKind::InvalidUtf8, 10),
+                (LintKind::MixedLineEndings, 10),
+                (LintKind::MissingFinalNewline, 12),
+            ]
+        );
+    }
+
+    #[test]
+    fn fix_applies_only_safe_rewrites() {
+        let input = b"one  \r\n%\n%\n% \ntwo\n%\nthree";
+        let fixed = fix_corpus(input, &LintConfig::default());
+        assert_eq!(fixed, b"one\n%\n% \ntwo\n%\nthree\n");
+        assert_eq!(kinds(&fixed), vec![(LintKind::DelimiterLike, 3)]);
+    }
+
+    #[test]
+    fn fix_keeps_clean_files_and_their_conventions() {
+        let config = LintConfig::default();
+        let clean = b"one\n%\ntwo\n%\n";
+        assert!(kinds(clean).is_empty());
+        assert_eq!(fix_corpus(clean, &config), clean);
+        assert_eq!(
+            fix_corpus(b"one\n%\n%\ntwo  \n%\n", &config),
+            b"one\n%\ntwo\n%\n"
+        );
+        assert_eq!(
+            fix_corpus(b"a\r\nb\r\n%\r\nc", &config),
+            b"a\r\nb\r\n%\r\nc\r\n"
+        );
+    }
+}
@@ -1,27 +1,33 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use std::thread;
 use std::time::Duration;
 
-use anyhow::{Result, bail};
+use anyhow::Result;
+use chrono::NaiveDateTime;
 use clap::{ArgAction, Parser};
 use regex::RegexBuilder;
 use tracing::{debug, info, instrument, warn};
 
+use rustune::auto_index::AutoIndex;
+use rustune::config::Config;
 use rustune::datfile::LengthFilter;
-use rustune::discovery::{DiscoveryConfig, discover_weighted_sources};
-use rustune::fortune_engine::{
-    FileSelectionMode, LoadedSource, calculate_probabilities, collect_matches, load_sources,
-    select_random_fortune,
-};
+use rustune::discovery::{DiscoveryConfig, Overlap};
+use rustune::discovery_cache::DiscoveryCache;
+use rustune::error::{Error, exit_code_for};
+use rustune::explain::{ExplainFormat, Explanation, source_decisions, source_probabilities};
+use rustune::fortune::{Fortune, Offensive, Replacement};
+use rustune::fortune_engine::{FortuneSelection, LoadedSource, RecordSelection, Weighting};
+use rustune::layout::{Layout, Overflow, terminal_columns};
 use rustune::logging::init_logging;
+use rustune::pacing::{Pacing, interrupted, parse_interval, parse_wait};
+use rustune::record::RecordFilter;
+use rustune::render::{Output, RenderContext, Style, Template};
 use rustune::rng::FortuneRng;
-use rustune::sources::{SourceSpec, parse_source_specs};
-
-const MIN_WAIT_SECONDS: usize = 6;
-const CHARS_PER_SECOND: usize = 20;
+use rustune::schedule::{Schedule, parse_timestamp};
+use rustune::simulate::{ChiSquare, Simulation, simulate};
+use rustune::sources::SourceSpec;
 
 #[derive(Debug, Parser)]
 #[command(name = "rustune")]
@@ -34,6 +40,16 @@ struct Args {
     offensive_only: bool,
     #[arg(short = 'e', long = "equal", action = ArgAction::SetTrue)]
     equal_probability: bool,
+    #[arg(
+        long = "weighting",
+        value_name = "STRATEGY",
+        conflicts_with = "equal_probability"
+    )]
+    weighting: Option<Weighting>,
+    #[arg(long = "overlap", value_name = "POLICY")]
+    overlap: Option<Overlap>,
+    #[arg(long = "record-selection", value_name = "MODE")]
+    record_selection: Option<RecordSelection>,
     #[arg(short = 'f', long = "files", action = ArgAction::SetTrue)]
     list_files: bool,
     #[arg(short = 'l', long = "long", action = ArgAction::SetTrue, conflicts_with = "short_only")]
@@ -46,14 +62,112 @@ struct Args {
     pattern: Option<String>,
     #[arg(short = 'i', long = "ignore-case", action = ArgAction::SetTrue)]
     ignore_case: bool,
+    #[arg(long = "author", value_name = "NAME")]
+    authors: Vec<String>,
+    #[arg(long = "tag", value_name = "TAG", allow_hyphen_values = true)]
+    tags: Vec<String>,
+    #[arg(long = "no-attribution", action = ArgAction::SetTrue)]
+    no_attribution: bool,
+    #[arg(
+        long = "wrap",
+        value_name = "COLS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0"
+    )]
+    wrap: Option<usize>,
+    #[arg(
+        long = "max-lines",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    max_lines: Option<usize>,
+    #[arg(long = "overflow", value_name = "MODE", default_value = "truncate")]
+    overflow: Overflow,
+    #[arg(long = "style", value_name = "STYLE", conflicts_with = "template")]
+    style: Option<Style>,
+    #[arg(long = "template", value_name = "TEMPLATE")]
+    template: Option<Template>,
     #[arg(short = 'w', long = "wait", action = ArgAction::SetTrue)]
     wait: bool,
+    #[arg(long = "wpm", value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    wpm: Option<u32>,
+    #[arg(long = "min-wait", value_name = "SECONDS", value_parser = parse_wait)]
+    min_wait: Option<Duration>,
+    #[arg(long = "max-wait", value_name = "SECONDS", value_parser = parse_wait)]
+    max_wait: Option<Duration>,
+    #[arg(long = "countdown", action = ArgAction::SetTrue)]
+    countdown: bool,
+    #[arg(long = "clear", action = ArgAction::SetTrue)]
+    clear: bool,
+    #[arg(
+        short = 'N',
+        long = "count",
+        value_name = "COUNT",
+        conflicts_with_all = ["list_files", "pattern", "loop_mode"]
+    )]
+    count: Option<usize>,
+    #[arg(long = "with-replacement", action = ArgAction::SetTrue, requires = "count")]
+    with_replacement: bool,
+    #[arg(
+        long = "explain",
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text",
+        conflicts_with_all = ["list_files", "pattern", "loop_mode", "count"]
+    )]
+    explain: Option<ExplainFormat>,
+    #[arg(
+        long = "simulate",
+        value_name = "DRAWS",
+        conflicts_with_all = ["list_files", "pattern", "loop_mode", "count"]
+    )]
+    simulate: Option<usize>,
+    #[arg(
+        long = "loop",
+        action = ArgAction::SetTrue,
+        conflicts_with_all = ["list_files", "pattern"]
+    )]
+    loop_mode: bool,
+    #[arg(
+        long = "interval",
+        value_name = "SECONDS",
+        requires = "loop_mode",
+        value_parser = parse_interval
+    )]
+    interval: Option<Duration>,
+    #[arg(long = "config", value_name = "PATH")]
+    config: Option<PathBuf>,
+    #[arg(long = "now", value_name = "TIME", value_parser = parse_timestamp)]
+    now: Option<NaiveDateTime>,
     #[arg(short = 'c', long = "show-source", action = ArgAction::SetTrue)]
     show_source: bool,
     #[arg(short = 'u', long = "no-recode", action = ArgAction::SetTrue)]
     no_recode: bool,
     #[arg(short = 'v', long = "version", action = ArgAction::SetTrue)]
     version_only: bool,
+    #[arg(
+        long = "auto-index",
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "memory"
+    )]
+    auto_index: Option<AutoIndex>,
+    #[arg(
+        long = "rebuild-cache",
+        action = ArgAction::SetTrue,
+        conflicts_with = "no_cache",
+        help = "Rebuild the default-search cache in $XDG_CACHE_HOME/rustune/discovery.json"
+    )]
+    rebuild_cache: bool,
+    #[arg(
+        long = "no-cache",
+        action = ArgAction::SetTrue,
+        help = "Neither read nor write the default-search cache"
+    )]
+    no_cache: bool,
     #[arg(long = "verbose", action = ArgAction::SetTrue)]
     verbose: bool,
     #[arg(value_name = "SOURCE")]
@@ -65,7 +179,7 @@ fn main() {
     init_logging(args.verbose, "warn,rustune=info");
     if let Err(err) = run(args) {
         eprintln!("rustune: {err:#}");
-        std::process::exit(1);
+        std::process::exit(exit_code_for(err.as_ref()));
     }
 }
 
@@ -81,73 +195,357 @@ fn run(args: Args) -> Result<()> {
     }
 
     if args.ignore_case && args.pattern.is_none() {
-        bail!("-i requires -m <pattern>");
+        return Err(Error::InvalidArgument("-i requires -m <pattern>".into()).into());
     }
 
-    let source_specs = parse_source_specs(&args.sources)?;
+    let config = Config::load(args.config.as_deref())?;
+    let record_selection = args
+        .record_selection
+        .or(config.selection.records)
+        .unwrap_or_default();
+    let weighting = if args.equal_probability {
+        Weighting::Equal
+    } else {
+        args.weighting
+            .or(config.selection.weighting)
+            .unwrap_or_default()
+    };
+    let pacing = args.wait.then(|| pacing_for(&args, &config)).transpose()?;
+
+    let schedule = Schedule::new(config.schedule.clone(), args.now);
     let discovery_cfg = DiscoveryConfig {
         allow_any: args.allow_any,
         offensive_only: args.offensive_only,
+        auto_index: args.auto_index.unwrap_or_default(),
+        overlap: args
+            .overlap
+            .or(config.selection.overlap)
+            .unwrap_or_default(),
+        schedule: Some(schedule.clone()),
     };
-    let discovered = discover_weighted_sources(&source_specs, &discovery_cfg)?;
     let length_filter = compute_length_filter(args.short_only, args.long_only, args.length);
-    let loaded = load_sources(&discovered, length_filter)?;
-    let probabilities = calculate_probabilities(&loaded, args.equal_probability)?;
+    let record_filter = RecordFilter {
+        authors: args.authors.clone(),
+        tags: args.tags.clone(),
+    };
+    let layout = Layout {
+        wrap: args
+            .wrap
+            .map(|cols| if cols == 0 { terminal_columns() } else { cols }),
+        max_lines: args.max_lines,
+        overflow: args.overflow,
+    };
+    let output = match (&args.template, args.style) {
+        (Some(template), _) => Output::Template(template.clone()),
+        (None, style) => Output::Style(style.unwrap_or_default()),
+    };
+
+    if uses_discovery_cache(&args, &discovery_cfg, &record_filter, &layout, weighting)
+        && let Some(cache_path) = DiscoveryCache::default_path()
+    {
+        let cache = DiscoveryCache::load_or_rebuild(&cache_path, args.rebuild_cache)?;
+        let mut rng = FortuneRng::from_env()?;
+        let (selection, text) = pick_fitting(&args, &layout, &output, || {
+            cache.select_random_fortune(
+                &discovery_cfg,
+                length_filter,
+                weighting,
+                record_selection,
+                &mut rng,
+            )
+        })?;
+        return emit_selection(&args, &output, pacing.as_ref(), &selection, &text);
+    }
+
+    let offensive = if args.offensive_only {
+        Offensive::Only
+    } else if args.allow_any {
+        Offensive::Include
+    } else {
+        Offensive::Exclude
+    };
+    let mut fortune = Fortune::builder()
+        .sources(args.sources.iter().cloned())
+        .offensive(offensive)
+        .length(length_filter)
+        .records(record_filter)
+        .weighting(weighting)
+        .record_selection(record_selection)
+        .auto_index(discovery_cfg.auto_index)
+        .overlap(discovery_cfg.overlap)
+        .schedule(schedule)
+        .fit(layout, printed_attribution(&args, &output))
+        .build()?;
 
     if args.list_files {
-        print_probabilities(&source_specs, &loaded, &probabilities)?;
+        print_probabilities(fortune.specs(), fortune.sources(), fortune.probabilities())?;
+        return Ok(());
+    }
+    // `-f` marks merged sources inline; everything else gets a warning.
+    for entry in fortune.sources().iter().filter(|e| e.merged_from > 1) {
+        eprintln!(
+            "rustune: warning: {} is named by {} source specs; merged with the {} policy",
+            source_label(
+                &entry.source.display_name(),
+                entry.source.metadata().path.as_deref()
+            ),
+            entry.merged_from,
+            discovery_cfg.overlap.label()
+        );
+    }
+
+    if let Some(draws) = args.simulate {
+        let simulation = simulate(&mut fortune, draws)?;
+        print_simulation(&simulation)?;
         return Ok(());
     }
 
     if let Some(pattern) = &args.pattern {
         let matcher = RegexBuilder::new(pattern)
             .case_insensitive(args.ignore_case)
-            .build()?;
-        let matches = collect_matches(&loaded, &matcher)?;
+            .build()
+            .map_err(Error::from)?;
+        let matches = fortune.search(&matcher)?;
         if matches.is_empty() {
             return Ok(());
         }
 
         let mut announced = BTreeSet::new();
         for matched in matches {
-            if announced.insert(matched.source_path.clone()) {
-                eprintln!("{}", matched.source_path.display());
+            let Some(text) = layout.apply(
+                &matched
+                    .metadata
+                    .printable(printed_attribution(&args, &output)),
+            ) else {
+                continue;
+            };
+            if announced.insert(matched.source_name.clone()) {
+                eprintln!("{}", matched.source_name);
             }
-            print_record(&matched.text)?;
+            print_record(&output.render(&RenderContext::for_match(
+                &matched,
+                &text,
+                !args.no_attribution,
+            )))?;
             println!("%");
         }
         return Ok(());
     }
 
-    let mut rng = FortuneRng::from_env()?;
-    let selection_mode =
-        if args.equal_probability || loaded.iter().any(|entry| entry.explicit_percent.is_some()) {
-            FileSelectionMode::ProbabilityPercent
+    if let Some(count) = args.count {
+        let replacement = if args.with_replacement {
+            Replacement::With
         } else {
-            FileSelectionMode::CandidateCount
+            Replacement::Without
+        };
+        return emit_batch(
+            &args,
+            &mut fortune,
+            replacement,
+            count,
+            &layout,
+            &output,
+            pacing.as_ref(),
+        );
+    }
+
+    if args.loop_mode {
+        let mut pacing = pacing_for(&args, &config)?;
+        if let Some(interval) = args.interval {
+            pacing.min_wait = interval;
+            pacing.max_wait = Some(interval);
+        }
+        return run_loop(&args, &mut fortune, &layout, &output, &pacing);
+    }
+
+    let Some(format) = args.explain else {
+        let (selection, text) = pick_fitting(&args, &layout, &output, || fortune.pick())?;
+        return emit_selection(&args, &output, pacing.as_ref(), &selection, &text);
+    };
+    let mut draw = None;
+    let (selection, text) = pick_fitting(&args, &layout, &output, || {
+        let (selection, trace) = fortune.pick_traced()?;
+        draw = Some(trace);
+        Ok(selection)
+    })?;
+    let explanation = Explanation {
+        sources: source_decisions(fortune.specs(), &discovery_cfg, fortune.sources())?,
+        mode: fortune.selection_mode().into(),
+        weighting: fortune.weighting(),
+        probabilities: source_probabilities(fortune.sources(), fortune.probabilities()),
+        draw: draw.unwrap_or_default(),
+    };
+    print_explanation(format, &explanation)?;
+    emit_selection(&args, &output, pacing.as_ref(), &selection, &text)
+}
+
+// Prints up to `count` records separated by `%` lines; without replacement
+// the batch ends early once every record has been drawn.
+fn emit_batch(
+    args: &Args,
+    fortune: &mut Fortune,
+    replacement: Replacement,
+    count: usize,
+    layout: &Layout,
+    output: &Output,
+    pacing: Option<&Pacing>,
+) -> Result<()> {
+    let mut picks = fortune.sample(replacement);
+    for emitted in 0..count {
+        let mut exhausted = false;
+        let picked = pick_fitting(args, layout, output, || {
+            picks.next().unwrap_or_else(|| {
+                exhausted = true;
+                Err(Error::FilterLeftNothing)
+            })
+        });
+        let (selection, text) = match picked {
+            Err(_) if exhausted && emitted > 0 => {
+                warn!(requested = count, emitted, "ran out of unique records");
+                break;
+            }
+            picked => picked?,
         };
-    let selection = select_random_fortune(&loaded, &probabilities, &mut rng, selection_mode)?;
+        if emitted > 0 {
+            println!("%");
+        }
+        emit_selection(args, output, pacing, &selection, &text)?;
+    }
+    Ok(())
+}
 
+// Cycles through every reachable record before any repeats, then starts a
+// fresh round; stops when a stop signal arrives.
+fn run_loop(
+    args: &Args,
+    fortune: &mut Fortune,
+    layout: &Layout,
+    output: &Output,
+    pacing: &Pacing,
+) -> Result<()> {
+    let mut picks = fortune.unique();
+    let mut shown = 0usize;
+    while !interrupted() {
+        let (selection, text) = pick_fitting(args, layout, output, || {
+            if picks.remaining() == 0 {
+                debug!(shown, "every record shown; starting a new round");
+                picks.reset();
+            }
+            picks.next().unwrap_or(Err(Error::FilterLeftNothing))
+        })?;
+        emit_selection(args, output, Some(pacing), &selection, &text)?;
+        shown += 1;
+    }
+    info!(shown, "loop stopped");
+    Ok(())
+}
+
+fn printed_attribution(args: &Args, output: &Output) -> bool {
+    !args.no_attribution && !output.credits_separately()
+}
+
+// Tall records are already filtered out of the candidate set when they
+// would be skipped, so this only renders the pick.
+fn pick_fitting(
+    args: &Args,
+    layout: &Layout,
+    output: &Output,
+    pick: impl FnOnce() -> rustune::Result<FortuneSelection>,
+) -> Result<(FortuneSelection, String)> {
+    let selection = pick()?;
+    let text = layout
+        .apply(
+            &selection
+                .metadata
+                .printable(printed_attribution(args, output)),
+        )
+        .ok_or(Error::FilterLeftNothing)?;
+    Ok((selection, text))
+}
+
+fn uses_discovery_cache(
+    args: &Args,
+    discovery_cfg: &DiscoveryConfig,
+    record_filter: &RecordFilter,
+    layout: &Layout,
+    weighting: Weighting,
+) -> bool {
+    // The cache only keeps candidate counts; it knows neither record sizes
+    // nor schedules.
+    args.sources.is_empty()
+        && record_filter.is_empty()
+        && !layout.skips_tall_records()
+        && weighting != Weighting::Bytes
+        && discovery_cfg
+            .schedule
+            .as_ref()
+            .is_none_or(Schedule::is_empty)
+        && !args.no_cache
+        && !args.list_files
+        && !args.loop_mode
+        && args.count.is_none()
+        && args.simulate.is_none()
+        && args.explain.is_none()
+        && args.pattern.is_none()
+        && !discovery_cfg.auto_index.enabled()
+}
+
+fn pacing_for(args: &Args, config: &Config) -> rustune::Result<Pacing> {
+    let mut pacing = Pacing::from_config(&config.wait);
+    if let Some(wpm) = args.wpm {
+        pacing.wpm = wpm;
+    }
+    if let Some(min_wait) = args.min_wait {
+        pacing.min_wait = min_wait;
+    }
+    if let Some(max_wait) = args.max_wait {
+        pacing.max_wait = Some(max_wait);
+    }
+    // Only bounds someone set can conflict; a lone --max-wait below the
+    // default minimum just caps the pause.
+    let min_set = args.min_wait.is_some() || config.wait.min_seconds.is_some();
+    if let Some(max_wait) = pacing.max_wait
+        && min_set
+        && max_wait < pacing.min_wait
+    {
+        return Err(Error::InvalidArgument(format!(
+            "--max-wait ({}s) is less than --min-wait ({}s)",
+            max_wait.as_secs_f64(),
+            pacing.min_wait.as_secs_f64()
+        )));
+    }
+    pacing.countdown |= args.countdown;
+    pacing.clear |= args.clear;
+    Ok(pacing)
+}
+
+fn emit_selection(
+    args: &Args,
+    output: &Output,
+    pacing: Option<&Pacing>,
+    selection: &FortuneSelection,
+    text: &str,
+) -> Result<()> {
     if args.show_source {
         println!(
             "({})",
-            absolute_display_path(&selection.source_path).display()
+            source_label(&selection.source_name, selection.source_path.as_deref())
         );
         println!("%");
     }
-    print_record(&selection.text)?;
+    print_record(&output.render(&RenderContext::for_selection(
+        selection,
+        text,
+        !args.no_attribution,
+    )))?;
     info!(
-        source = %selection.source_path.display(),
+        source = %selection.source_name,
         index = selection.record_index,
         "fortune emitted"
     );
 
-    if args.wait {
-        let sleep_s = wait_seconds_for_text(&selection.text);
-        debug!(sleep_s, "sleeping for -w output pacing");
-        if sleep_s > 0 {
-            thread::sleep(Duration::from_secs(sleep_s as u64));
-        }
+    if let Some(pacing) = pacing {
+        pacing.pause(text)?;
     }
 
     Ok(())
@@ -163,6 +561,124 @@ fn compute_length_filter(short_only: bool, long_only: bool, threshold: usize) ->
     }
 }
 
+fn print_explanation(format: ExplainFormat, explanation: &Explanation) -> Result<()> {
+    let mut err = io::stderr().lock();
+    if format == ExplainFormat::Json {
+        serde_json::to_writer_pretty(&mut err, explanation)?;
+        writeln!(err)?;
+        return Ok(());
+    }
+
+    writeln!(err, "sources:")?;
+    for decision in &explanation.sources {
+        writeln!(
+            err,
+            "  {:<14} {}",
+            decision.status.label(),
+            decision.path.display()
+        )?;
+    }
+    writeln!(
+        err,
+        "mode: {} (weighting {})",
+        explanation.mode.label(),
+        explanation.weighting.label()
+    )?;
+    writeln!(err, "probabilities:")?;
+    for probability in &explanation.probabilities {
+        let explicit = probability
+            .explicit_percent
+            .map(|percent| format!(", explicit {percent:.2}%"))
+            .or_else(|| {
+                probability
+                    .relative_weight
+                    .map(|weight| format!(", weight {weight}"))
+            })
+            .unwrap_or_default();
+        writeln!(
+            err,
+            "  {:>6.2}% {} ({} candidates{explicit})",
+            probability.percent, probability.source, probability.candidates
+        )?;
+    }
+    let draw = &explanation.draw;
+    writeln!(
+        err,
+        "source draw: {} of {}",
+        draw.source_draw, draw.source_range
+    )?;
+    for step in &draw.walk {
+        let verdict = if step.source == draw.chosen_source {
+            "chosen"
+        } else {
+            "passed"
+        };
+        let name = explanation
+            .probabilities
+            .get(step.source)
+            .map_or("?", |probability| probability.source.as_str());
+        writeln!(
+            err,
+            "  marker {:.2} vs weight {:.2}: {verdict} {name}",
+            step.marker, step.weight
+        )?;
+    }
+    match draw.record_selection {
+        RecordSelection::Parity => writeln!(
+            err,
+            "record draw: {} of {} -> start at #{} (get_pos +1), skipped {} filtered records -> #{}",
+            draw.record_draw,
+            draw.record_total,
+            draw.initial_index,
+            draw.skipped_records,
+            draw.record_index
+        )?,
+        RecordSelection::Fair => writeln!(
+            err,
+            "record draw: {} of {} candidates (fair) -> #{}",
+            draw.record_draw, draw.record_total, draw.record_index
+        )?,
+    }
+    Ok(())
+}
+
+fn print_simulation(simulation: &Simulation) -> Result<()> {
+    let mut out = io::stdout().lock();
+    let draws = simulation.draws.max(1) as f64;
+    writeln!(out, "{} draws", simulation.draws)?;
+    writeln!(out, "{:>9} {:>9}  source", "expected", "observed")?;
+    for source in &simulation.sources {
+        writeln!(
+            out,
+            "{:>8.2}% {:>8.2}%  {}",
+            source.expected * 100.0,
+            source.observed as f64 / draws * 100.0,
+            source.name
+        )?;
+        for record in &source.records {
+            writeln!(
+                out,
+                "{:>8.2}% {:>8.2}%    #{}",
+                record.expected * 100.0,
+                record.observed as f64 / draws * 100.0,
+                record.index
+            )?;
+        }
+    }
+    print_chi_square(&mut out, "sources", &simulation.source_test)?;
+    print_chi_square(&mut out, "records", &simulation.record_test)?;
+    Ok(())
+}
+
+fn print_chi_square(out: &mut impl Write, label: &str, test: &ChiSquare) -> Result<()> {
+    writeln!(
+        out,
+        "{label}: chi-square {:.3} (df {}), p = {:.4}",
+        test.statistic, test.degrees_of_freedom, test.p_value
+    )?;
+    Ok(())
+}
+
 fn print_probabilities(
     source_specs: &[SourceSpec],
     loaded: &[LoadedSource],
@@ -170,32 +686,103 @@ fn print_probabilities(
 ) -> Result<()> {
     let mut err = io::stderr().lock();
 
+    let shares = effective_shares(loaded, probabilities);
     if source_specs.len() == 1 && source_specs[0].path.is_dir() {
         let total: f64 = probabilities.iter().sum();
         let top = absolute_display_path(&source_specs[0].path);
         writeln!(err, "{:.2}% {}", total, top.display())?;
 
-        for (entry, probability) in loaded.iter().zip(probabilities.iter()) {
+        for share in &shares {
             let rel = if total > 0.0 {
-                (probability / total) * 100.0
+                (share.probability / total) * 100.0
             } else {
                 0.0
             };
-            let label = entry
-                .db
-                .text_path
-                .file_name()
-                .map(|s| s.to_string_lossy().into_owned())
-                .unwrap_or_else(|| entry.db.text_path.display().to_string());
-            writeln!(err, "    {:.2}% {}", rel, label)?;
+            let label = share
+                .entry
+                .source
+                .metadata()
+                .path
+                .and_then(|path| path.file_name().map(|s| s.to_string_lossy().into_owned()))
+                .unwrap_or_else(|| share.entry.source.display_name());
+            writeln!(err, "    {:.2}% {}{}", rel, label, share.suffix())?;
+            print_tag_counts(&mut err, "        ", share.entry)?;
         }
         return Ok(());
     }
 
-    for (entry, probability) in loaded.iter().zip(probabilities.iter()) {
-        let abs = absolute_display_path(&entry.db.text_path);
-        writeln!(err, "{:.2}% {}", probability, abs.display())?;
+    for share in &shares {
+        writeln!(
+            err,
+            "{:.2}% {}{}",
+            share.probability,
+            share.label,
+            share.suffix()
+        )?;
+        print_tag_counts(&mut err, "    ", share.entry)?;
+    }
+    Ok(())
+}
+
+struct EffectiveShare<'a> {
+    label: String,
+    entry: &'a LoadedSource,
+    probability: f64,
+    entries: usize,
+}
+
+impl EffectiveShare<'_> {
+    fn suffix(&self) -> String {
+        if self.entries > 1 {
+            format!(" (merged from {} specs)", self.entries)
+        } else {
+            String::new()
+        }
+    }
+}
+
+// A file named by several specs (listed twice, or also inside a listed
+// directory) is shown once with its combined share.
+fn effective_shares<'a>(
+    loaded: &'a [LoadedSource],
+    probabilities: &[f64],
+) -> Vec<EffectiveShare<'a>> {
+    let mut shares: Vec<EffectiveShare<'a>> = Vec::new();
+    let mut by_label: HashMap<String, usize> = HashMap::new();
+    for (entry, probability) in loaded.iter().zip(probabilities) {
+        let label = source_label(
+            &entry.source.display_name(),
+            entry.source.metadata().path.as_deref(),
+        );
+        match by_label.get(&label) {
+            Some(&slot) => {
+                shares[slot].probability += probability;
+                shares[slot].entries += entry.merged_from;
+            }
+            None => {
+                by_label.insert(label.clone(), shares.len());
+                shares.push(EffectiveShare {
+                    label,
+                    entry,
+                    probability: *probability,
+                    entries: entry.merged_from,
+                });
+            }
+        }
+    }
+    shares
+}
+
+fn print_tag_counts(out: &mut dyn Write, indent: &str, entry: &LoadedSource) -> Result<()> {
+    let counts = entry.tag_counts()?;
+    if counts.is_empty() {
+        return Ok(());
     }
+    let listed: Vec<String> = counts
+        .iter()
+        .map(|(tag, count)| format!("{tag} ({count})"))
+        .collect();
+    writeln!(out, "{indent}tags: {}", listed.join(", "))?;
     Ok(())
 }
 
@@ -208,10 +795,11 @@ fn print_record(text: &str) -> Result<()> {
     Ok(())
 }
 
-fn wait_seconds_for_text(text: &str) -> usize {
-    let chars = text.chars().count();
-    let cps = chars.div_ceil(CHARS_PER_SECOND);
-    cps.max(MIN_WAIT_SECONDS)
+fn source_label(name: &str, path: Option<&Path>) -> String {
+    match path {
+        Some(path) => absolute_display_path(path).display().to_string(),
+        None => name.to_string(),
+    }
 }
 
 fn absolute_display_path(path: &Path) -> PathBuf {
@@ -0,0 +1,266 @@
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use tracing::{debug, warn};
+
+use crate::config::WaitConfig;
+use crate::error::{Error, Result};
+
+pub const DEFAULT_WPM: u32 = 200;
+pub const DEFAULT_MIN_WAIT: Duration = Duration::from_secs(6);
+const TICK: Duration = Duration::from_secs(1);
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+#[cfg(unix)]
+const STOP_SIGNALS: &[i32] = &[
+    signal_hook::consts::SIGINT,
+    signal_hook::consts::SIGTERM,
+    signal_hook::consts::SIGHUP,
+];
+#[cfg(not(unix))]
+const STOP_SIGNALS: &[i32] = &[signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pacing {
+    pub wpm: u32,
+    pub min_wait: Duration,
+    pub max_wait: Option<Duration>,
+    pub countdown: bool,
+    pub clear: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitEnd {
+    Elapsed,
+    Key,
+    Interrupted,
+}
+
+impl Default for Pacing {
+    fn default() -> Self {
+        Self {
+            wpm: DEFAULT_WPM,
+            min_wait: DEFAULT_MIN_WAIT,
+            max_wait: None,
+            countdown: false,
+            clear: false,
+        }
+    }
+}
+
+impl Pacing {
+    pub fn from_config(config: &WaitConfig) -> Self {
+        let defaults = Self::default();
+        Self {
+            wpm: config.wpm.unwrap_or(defaults.wpm),
+            min_wait: config.min_seconds.map_or(defaults.min_wait, seconds),
+            max_wait: config.max_seconds.map(seconds),
+            countdown: config.countdown.unwrap_or(defaults.countdown),
+            clear: config.clear.unwrap_or(defaults.clear),
+        }
+    }
+
+    pub fn wait_for(&self, text: &str) -> Duration {
+        let words = text.split_whitespace().count() as f64;
+        let reading = Duration::from_secs_f64(words * 60.0 / f64::from(self.wpm.max(1)));
+        let wait = reading.max(self.min_wait);
+        match self.max_wait {
+            Some(max) => wait.min(max),
+            None => wait,
+        }
+    }
+
+    pub fn pause(&self, text: &str) -> io::Result<WaitEnd> {
+        let wait = self.wait_for(text);
+        debug!(wait_ms = wait.as_millis() as u64, "pausing after output");
+        io::stdout().flush()?;
+        let end = interruptible_sleep(wait, self.countdown)?;
+        if self.clear && end != WaitEnd::Interrupted {
+            let mut out = io::stdout().lock();
+            out.write_all(CLEAR_SCREEN.as_bytes())?;
+            out.flush()?;
+        }
+        Ok(end)
+    }
+}
+
+pub fn interrupted() -> bool {
+    signal_flag().is_some_and(|flag| flag.load(Ordering::Relaxed))
+}
+
+// Sleeps until the deadline, a key press on a terminal stdin, or a stop signal.
+pub fn interruptible_sleep(duration: Duration, countdown: bool) -> io::Result<WaitEnd> {
+    let deadline = Instant::now() + duration;
+    let raw = RawInput::enable();
+    let mut stderr = io::stderr();
+    let end = loop {
+        if interrupted() {
+            break WaitEnd::Interrupted;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break WaitEnd::Elapsed;
+        }
+        if countdown {
+            write!(stderr, "\r{:>3}s ", remaining.as_secs_f64().ceil() as u64)?;
+            stderr.flush()?;
+        }
+        // With a countdown, wake on each whole-second boundary to redraw it.
+        let slice = match (remaining.as_nanos() % TICK.as_nanos()) as u64 {
+            _ if !countdown => remaining,
+            0 => TICK,
+            fraction => Duration::from_nanos(fraction),
+        };
+        if wait_for_key(slice, raw.is_some()) {
+            break WaitEnd::Key;
+        }
+    };
+    if countdown {
+        write!(stderr, "\r      \r")?;
+        stderr.flush()?;
+    }
+    debug!(?end, "wait finished");
+    Ok(end)
+}
+
+// `--interval` seconds; zero would print fortunes as fast as the terminal
+// accepts them.
+pub fn parse_interval(value: &str) -> Result<Duration> {
+    parse_seconds(value, false)
+}
+
+// `--min-wait` and `--max-wait` seconds, where zero turns the pause off.
+pub fn parse_wait(value: &str) -> Result<Duration> {
+    parse_seconds(value, true)
+}
+
+fn parse_seconds(value: &str, allow_zero: bool) -> Result<Duration> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && (*secs > 0.0 || (allow_zero && *secs == 0.0)))
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| {
+            let expected = if allow_zero {
+                "non-negative"
+            } else {
+                "positive"
+            };
+            Error::InvalidArgument(format!(
+                "invalid number of seconds '{value}' (expected a {expected} number)"
+            ))
+        })
+}
+
+fn seconds(value: f64) -> Duration {
+    Duration::try_from_secs_f64(value).unwrap_or(Duration::ZERO)
+}
+
+fn signal_flag() -> Option<&'static Arc<AtomicBool>> {
+    static FLAG: OnceLock<Option<Arc<AtomicBool>>> = OnceLock::new();
+    FLAG.get_or_init(|| {
+        let flag = Arc::new(AtomicBool::new(false));
+        for signal in STOP_SIGNALS {
+            if let Err(err) = signal_hook::flag::register(*signal, Arc::clone(&flag)) {
+                warn!(%err, signal, "cannot install signal handler; waits are not interruptible");
+                return None;
+            }
+        }
+        Some(flag)
+    })
+    .as_ref()
+}
+
+#[cfg(unix)]
+fn wait_for_key(timeout: Duration, watch_stdin: bool) -> bool {
+    let mut fd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let nfds = libc::nfds_t::from(watch_stdin);
+    let millis = timeout.as_millis().clamp(1, i32::MAX as u128) as i32;
+    // SAFETY: `fd` is a valid pollfd for the duration of the call.
+    let ready = unsafe { libc::poll(&mut fd, nfds, millis) };
+    if ready > 0 && fd.revents & libc::POLLIN != 0 {
+        let mut byte = 0u8;
+        // SAFETY: reads at most one byte into a local buffer.
+        unsafe { libc::read(libc::STDIN_FILENO, (&mut byte as *mut u8).cast(), 1) };
+        return true;
+    }
+    false
+}
+
+#[cfg(not(unix))]
+fn wait_for_key(timeout: Duration, _watch_stdin: bool) -> bool {
+    std::thread::sleep(timeout.min(Duration::from_millis(100)));
+    false
+}
+
+// Puts a terminal stdin into non-canonical, no-echo mode so a single key
+// ends the wait; restores the previous settings on drop.
+struct RawInput {
+    #[cfg(unix)]
+    saved: libc::termios,
+}
+
+impl RawInput {
+    #[cfg(unix)]
+    fn enable() -> Option<Self> {
+        // SAFETY: termios calls only read and write the local struct for a
+        // descriptor that is checked to be a terminal first.
+        unsafe {
+            if libc::isatty(libc::STDIN_FILENO) != 1 {
+                return None;
+            }
+            let mut saved: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut saved) != 0 {
+                return None;
+            }
+            let mut raw = saved;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return None;
+            }
+            Some(Self { saved })
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn enable() -> Option<Self> {
+        None
+    }
+}
+
+impl Drop for RawInput {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: restores settings previously read from the same descriptor.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.saved);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_scales_with_words_and_respects_bounds() {
+        let pacing = Pacing {
+            wpm: 120,
+            min_wait: Duration::from_secs(1),
+            max_wait: Some(Duration::from_secs(5)),
+            ..Pacing::default()
+        };
+        assert_eq!(pacing.wait_for("one two"), Duration::from_secs(1));
+        assert_eq!(pacing.wait_for(&"word ".repeat(6)), Duration::from_secs(3));
+        assert_eq!(pacing.wait_for(&"word ".repeat(60)), Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,155 @@
+const TAGS_HEADER: &str = "#tags:";
+const LANG_HEADER: &str = "#lang:";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordMetadata {
+    pub body: String,
+    pub attribution: Option<Attribution>,
+    pub tags: Vec<String>,
+    pub lang: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribution {
+    pub author: String,
+    pub work: Option<String>,
+    pub raw: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordFilter {
+    pub authors: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+impl RecordMetadata {
+    pub fn parse(text: &str) -> Self {
+        let mut meta = Self::default();
+        let mut body_start = 0;
+        for line in text.split_inclusive('\n') {
+            let trimmed = line.trim();
+            if let Some(tags) = trimmed.strip_prefix(TAGS_HEADER) {
+                for tag in tags.split(',').map(normalize_tag) {
+                    if !tag.is_empty() && !meta.tags.contains(&tag) {
+                        meta.tags.push(tag);
+                    }
+                }
+            } else if let Some(lang) = trimmed.strip_prefix(LANG_HEADER) {
+                let lang = lang.trim();
+                meta.lang = (!lang.is_empty()).then(|| lang.to_string());
+            } else {
+                break;
+            }
+            body_start += line.len();
+        }
+
+        let rest = &text[body_start..];
+        let content_end = rest.trim_end().len();
+        let last_line_start = rest[..content_end].rfind('\n').map_or(0, |pos| pos + 1);
+        let body_end = match parse_attribution(&rest[last_line_start..]) {
+            Some(attribution) if last_line_start > 0 => {
+                meta.attribution = Some(attribution);
+                last_line_start
+            }
+            _ => rest.len(),
+        };
+        meta.body = rest[..body_end].to_string();
+        meta
+    }
+
+    pub fn printable(&self, with_attribution: bool) -> String {
+        match (&self.attribution, with_attribution) {
+            (Some(attribution), true) => format!("{}{}", self.body, attribution.raw),
+            _ => self.body.clone(),
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own == tag)
+    }
+}
+
+impl RecordFilter {
+    pub fn is_empty(&self) -> bool {
+        self.authors.is_empty() && self.tags.is_empty()
+    }
+
+    pub fn accepts(&self, meta: &RecordMetadata) -> bool {
+        let author_ok = self.authors.is_empty()
+            || meta.attribution.as_ref().is_some_and(|attribution| {
+                let author = attribution.author.to_lowercase();
+                self.authors
+                    .iter()
+                    .any(|wanted| author.contains(&wanted.to_lowercase()))
+            });
+        author_ok
+            && self
+                .tag_terms()
+                .all(|(tag, wanted)| meta.has_tag(&tag) == wanted)
+    }
+
+    pub fn tag_terms(&self) -> impl Iterator<Item = (String, bool)> + '_ {
+        self.tags
+            .iter()
+            .map(|tag| match tag.trim().strip_prefix('-') {
+                Some(excluded) => (normalize_tag(excluded), false),
+                None => (normalize_tag(tag), true),
+            })
+    }
+}
+
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+fn parse_attribution(line: &str) -> Option<Attribution> {
+    let credit = line
+        .trim_start()
+        .strip_prefix("--")
+        .or_else(|| line.trim_start().strip_prefix('—'))?
+        .trim();
+    if credit.is_empty() {
+        return None;
+    }
+    let (author, work) = match credit.split_once(", ") {
+        Some((author, work)) => (author.trim(), Some(work.trim().to_string())),
+        None => (credit, None),
+    };
+    Some(Attribution {
+        author: author.to_string(),
+        work,
+        raw: line.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_front_matter_and_attribution() {
+        let text =
+            "#tags: Humor, programming\n#lang: en\nTwo hard things.\n\t\t-- Phil Karlton, Talk\n";
+        let meta = RecordMetadata::parse(text);
+        assert_eq!(meta.tags, vec!["humor", "programming"]);
+        assert_eq!(meta.lang.as_deref(), Some("en"));
+        assert_eq!(meta.body, "Two hard things.\n");
+        let attribution = meta.attribution.as_ref().expect("attribution");
+        assert_eq!(attribution.author, "Phil Karlton");
+        assert_eq!(attribution.work.as_deref(), Some("Talk"));
+        assert_eq!(
+            meta.printable(true),
+            "Two hard things.\n\t\t-- Phil Karlton, Talk\n"
+        );
+
+        let filter = RecordFilter {
+            authors: vec!["karlton".into()],
+            tags: vec!["Humor".into(), "-politics".into()],
+        };
+        assert!(filter.accepts(&meta));
+
+        let plain = RecordMetadata::parse("-- not an attribution\n");
+        assert!(plain.attribution.is_none());
+        assert_eq!(plain.printable(true), "-- not an attribution\n");
+    }
+}
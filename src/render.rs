@@ -0,0 +1,411 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::fortune_engine::{FortuneSelection, MatchRecord};
+use crate::layout::{display_width, expand_tabs};
+use crate::record::RecordMetadata;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Style {
+    #[default]
+    Plain,
+    Box,
+    Bubble,
+    Quote,
+    Markdown,
+    Html,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Literal(String),
+    Field(Field),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Text,
+    Body,
+    Author,
+    Work,
+    Source,
+    Path,
+    Index,
+    Tags,
+    Lang,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    Style(Style),
+    Template(Template),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RenderContext<'a> {
+    pub text: &'a str,
+    pub source_name: &'a str,
+    pub source_path: Option<&'a Path>,
+    pub record_index: usize,
+    pub metadata: &'a RecordMetadata,
+    pub attribution: bool,
+}
+
+impl FromStr for Style {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "plain" => Ok(Self::Plain),
+            "box" => Ok(Self::Box),
+            "bubble" => Ok(Self::Bubble),
+            "quote" => Ok(Self::Quote),
+            "markdown" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            other => Err(Error::InvalidArgument(format!(
+                "unknown style '{other}' (expected plain|box|bubble|quote|markdown|html)"
+            ))),
+        }
+    }
+}
+
+impl FromStr for Template {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut chars = value.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' => match chars.next() {
+                    Some('n') => literal.push('\n'),
+                    Some('t') => literal.push('\t'),
+                    Some(other) => literal.push(other),
+                    None => literal.push('\\'),
+                },
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let Some(end) = rest.find('}') else {
+                        return Err(Error::InvalidArgument(format!(
+                            "unterminated template field in '{value}'"
+                        )));
+                    };
+                    let name = &rest[..end];
+                    chars = rest[end + 1..].chars();
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(Piece::Field(name.parse()?));
+                }
+                '}' => {
+                    literal.push('}');
+                    if chars.as_str().starts_with('}') {
+                        chars.next();
+                    }
+                }
+                other => literal.push(other),
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(Self { pieces })
+    }
+}
+
+impl FromStr for Field {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "text" => Ok(Self::Text),
+            "body" => Ok(Self::Body),
+            "author" => Ok(Self::Author),
+            "work" => Ok(Self::Work),
+            "source" => Ok(Self::Source),
+            "path" => Ok(Self::Path),
+            "index" => Ok(Self::Index),
+            "tags" => Ok(Self::Tags),
+            "lang" => Ok(Self::Lang),
+            other => Err(Error::InvalidArgument(format!(
+                "unknown template field '{{{other}}}' (expected text, body, author, work, source, path, index, tags or lang)"
+            ))),
+        }
+    }
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self::Style(Style::Plain)
+    }
+}
+
+impl Output {
+    // Quote-like styles and templates place the attribution themselves, so
+    // the record text they receive should be the body alone.
+    pub fn credits_separately(&self) -> bool {
+        matches!(
+            self,
+            Self::Style(Style::Quote | Style::Markdown | Style::Html) | Self::Template(_)
+        )
+    }
+
+    pub fn render(&self, ctx: &RenderContext<'_>) -> String {
+        match self {
+            Self::Style(style) => style.render(ctx),
+            Self::Template(template) => template.render(ctx),
+        }
+    }
+}
+
+impl<'a> RenderContext<'a> {
+    pub fn for_selection(
+        selection: &'a FortuneSelection,
+        text: &'a str,
+        attribution: bool,
+    ) -> Self {
+        Self {
+            text,
+            source_name: &selection.source_name,
+            source_path: selection.source_path.as_deref(),
+            record_index: selection.record_index,
+            metadata: &selection.metadata,
+            attribution,
+        }
+    }
+
+    pub fn for_match(matched: &'a MatchRecord, text: &'a str, attribution: bool) -> Self {
+        Self {
+            text,
+            source_name: &matched.source_name,
+            source_path: matched.source_path.as_deref(),
+            record_index: matched.record_index,
+            metadata: &matched.metadata,
+            attribution,
+        }
+    }
+
+    fn credit(&self) -> Option<String> {
+        let attribution = self
+            .metadata
+            .attribution
+            .as_ref()
+            .filter(|_| self.attribution)?;
+        Some(match &attribution.work {
+            Some(work) => format!("{}, {work}", attribution.author),
+            None => attribution.author.clone(),
+        })
+    }
+}
+
+impl Style {
+    fn render(self, ctx: &RenderContext<'_>) -> String {
+        let lines: Vec<String> = ctx
+            .text
+            .trim_end_matches('\n')
+            .lines()
+            .map(expand_tabs)
+            .collect();
+        match self {
+            Self::Plain => ctx.text.to_string(),
+            Self::Box => {
+                let width = max_width(&lines);
+                let mut out = format!("┌{}┐\n", "─".repeat(width + 2));
+                for line in &lines {
+                    out.push_str(&format!("│ {} │\n", pad(line, width)));
+                }
+                out.push_str(&format!("└{}┘\n", "─".repeat(width + 2)));
+                out
+            }
+            Self::Bubble => {
+                let width = max_width(&lines);
+                let mut out = format!(" {}\n", "_".repeat(width + 2));
+                let last = lines.len().saturating_sub(1);
+                for (idx, line) in lines.iter().enumerate() {
+                    let (open, close) = match (idx, lines.len()) {
+                        (_, 1) => ('<', '>'),
+                        (0, _) => ('/', '\\'),
+                        (idx, _) if idx == last => ('\\', '/'),
+                        _ => ('|', '|'),
+                    };
+                    out.push_str(&format!("{open} {} {close}\n", pad(line, width)));
+                }
+                out.push_str(&format!(" {}\n", "-".repeat(width + 2)));
+                out
+            }
+            Self::Quote => {
+                let mut out = format!("“{}”\n", lines.join("\n"));
+                if let Some(credit) = ctx.credit() {
+                    out.push_str(&format!("    — {credit}\n"));
+                }
+                out
+            }
+            Self::Markdown => {
+                let mut out = String::new();
+                for line in &lines {
+                    out.push_str(if line.is_empty() { ">\n" } else { "> " });
+                    if !line.is_empty() {
+                        out.push_str(line);
+                        out.push('\n');
+                    }
+                }
+                if let Some(credit) = ctx.credit() {
+                    out.push_str(&format!(">\n> — *{credit}*\n"));
+                }
+                out
+            }
+            Self::Html => {
+                let body: Vec<String> = lines.iter().map(|line| escape_html(line)).collect();
+                let mut out = format!(
+                    "<blockquote class=\"fortune\">\n<p>{}</p>\n",
+                    body.join("<br>\n")
+                );
+                if let Some(credit) = ctx.credit() {
+                    out.push_str(&format!("<footer>— {}</footer>\n", escape_html(&credit)));
+                }
+                out.push_str("</blockquote>\n");
+                out
+            }
+        }
+    }
+}
+
+impl Template {
+    fn render(&self, ctx: &RenderContext<'_>) -> String {
+        let mut out = String::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Literal(literal) => out.push_str(literal),
+                Piece::Field(field) => out.push_str(&field.value(ctx)),
+            }
+        }
+        out
+    }
+}
+
+impl Field {
+    fn value(self, ctx: &RenderContext<'_>) -> String {
+        let attribution = ctx
+            .metadata
+            .attribution
+            .as_ref()
+            .filter(|_| ctx.attribution);
+        match self {
+            Self::Text => ctx.text.trim_end_matches('\n').to_string(),
+            Self::Body => ctx.metadata.body.trim_end_matches('\n').to_string(),
+            Self::Author => attribution.map(|a| a.author.clone()).unwrap_or_default(),
+            Self::Work => attribution.and_then(|a| a.work.clone()).unwrap_or_default(),
+            Self::Source => ctx.source_name.to_string(),
+            Self::Path => ctx
+                .source_path
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            Self::Index => ctx.record_index.to_string(),
+            Self::Tags => ctx.metadata.tags.join(", "),
+            Self::Lang => ctx.metadata.lang.clone().unwrap_or_default(),
+        }
+    }
+}
+
+fn max_width(lines: &[String]) -> usize {
+    lines
+        .iter()
+        .map(|line| display_width(line))
+        .max()
+        .unwrap_or(0)
+}
+
+fn pad(line: &str, width: usize) -> String {
+    format!("{line}{}", " ".repeat(width - display_width(line)))
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context<'a>(text: &'a str, metadata: &'a RecordMetadata) -> RenderContext<'a> {
+        RenderContext {
+            text,
+            source_name: "quotes",
+            source_path: None,
+            record_index: 3,
+            metadata,
+            attribution: true,
+        }
+    }
+
+    #[test]
+    fn styles_pad_borders_by_display_width() {
+        let meta = RecordMetadata::parse("日本\nhi <b>\n\t\t-- Anon, Book\n");
+        let body = meta.body.clone();
+        let ctx = context(&body, &meta);
+        assert_eq!(
+            Style::Box.render(&ctx),
+            "┌────────┐\n│ 日本   │\n│ hi <b> │\n└────────┘\n"
+        );
+        assert_eq!(
+            Style::Bubble.render(&ctx),
+            " ________\n/ 日本   \\\n\\ hi <b> /\n --------\n"
+        );
+        assert_eq!(
+            Style::Quote.render(&ctx),
+            "“日本\nhi <b>”\n    — Anon, Book\n"
+        );
+        assert_eq!(
+            Style::Html.render(&ctx),
+            "<blockquote class=\"fortune\">\n<p>日本<br>\nhi &lt;b&gt;</p>\n<footer>— Anon, Book</footer>\n</blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn template_expands_fields_and_escapes() {
+        let meta = RecordMetadata::parse("#tags: a, b\nWisdom.\n-- Sage\n");
+        let ctx = context("Wisdom.\n", &meta);
+        let template: Template = r"{text}\n  — {author} ({source}#{index}) {{{tags}}}"
+            .parse()
+            .expect("template");
+        assert_eq!(
+            Output::Template(template).render(&ctx),
+            "Wisdom.\n  — Sage (quotes#3) {a, b}"
+        );
+        assert!("{nope}".parse::<Template>().is_err());
+
+        let template: Template = "{text} / {author}, {work}".parse().expect("template");
+        let output = Output::Template(template);
+        assert!(output.credits_separately());
+        let meta = RecordMetadata::parse("Be brief.\n\t\t-- Oscar Wilde, Notes\n");
+        let ctx = context(&meta.body, &meta);
+        assert_eq!(output.render(&ctx), "Be brief. / Oscar Wilde, Notes");
+        let ctx = RenderContext {
+            attribution: false,
+            ..ctx
+        };
+        assert_eq!(output.render(&ctx), "Be brief. / , ");
+    }
+}
@@ -1,15 +1,16 @@
 use std::env;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::{Result, bail};
 use rand::rngs::StdRng;
 use rand::{RngExt, SeedableRng};
 use tracing::{debug, instrument, trace};
 
+use crate::error::{Error, Result};
+
 #[derive(Debug)]
 enum Mode {
     HardCoded(u64),
-    Seeded(StdRng),
+    Seeded(Box<StdRng>),
     Thread,
 }
 
@@ -37,7 +38,7 @@ impl FortuneRng {
             let seed = secs ^ (std::process::id() as u64);
             debug!(seed, "using srand-compatible seeded RNG mode");
             return Ok(Self {
-                mode: Mode::Seeded(StdRng::seed_from_u64(seed)),
+                mode: Mode::Seeded(Box::new(StdRng::seed_from_u64(seed))),
             });
         }
 
@@ -45,6 +46,12 @@ impl FortuneRng {
         Ok(Self { mode: Mode::Thread })
     }
 
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            mode: Mode::Seeded(Box::new(StdRng::seed_from_u64(seed))),
+        }
+    }
+
     pub fn next_u64(&mut self) -> u64 {
         match &mut self.mode {
             Mode::HardCoded(value) => {
@@ -72,18 +79,19 @@ impl FortuneRng {
 fn parse_hardcoded_value(raw: &str) -> Result<u64> {
     let token = raw
         .split(|c: char| c == ',' || c == ';' || c.is_ascii_whitespace())
-        .filter(|x| !x.is_empty())
-        .next()
+        .find(|x| !x.is_empty())
         .ok_or_else(|| {
-            anyhow::anyhow!(
-                "FORTUNE_MOD_RAND_HARD_CODED_VALS is set but contains no numeric values"
+            Error::InvalidArgument(
+                "FORTUNE_MOD_RAND_HARD_CODED_VALS is set but contains no numeric values".into(),
             )
         })?;
     let parsed = token
         .parse::<u64>()
-        .map_err(|_| anyhow::anyhow!("invalid hardcoded RNG value '{token}'"))?;
+        .map_err(|_| Error::InvalidArgument(format!("invalid hardcoded RNG value '{token}'")))?;
     if raw.contains(',') || raw.contains(';') {
-        bail!("FORTUNE_MOD_RAND_HARD_CODED_VALS accepts a single numeric value");
+        return Err(Error::InvalidArgument(
+            "FORTUNE_MOD_RAND_HARD_CODED_VALS accepts a single numeric value".into(),
+        ));
     }
     Ok(parsed)
 }
@@ -0,0 +1,332 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, Timelike};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::discovery::canonical_key;
+use crate::error::{Error, Result};
+use crate::sources::WeightedSource;
+
+const TIMESTAMP_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    pub rules: Vec<ScheduleRule>,
+    pub now: NaiveDateTime,
+}
+
+// One `[[schedule]]` entry: the named source is only selectable while the
+// rule is active, and then takes the rule's percentage or weight if given.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleRule {
+    pub source: PathBuf,
+    pub dates: Option<DateRange>,
+    pub cron: Option<Cron>,
+    pub percent: Option<f64>,
+    pub weight: Option<f64>,
+}
+
+// Inclusive `MM-DD..MM-DD`; a range that ends before it starts wraps
+// across the new year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct DateRange {
+    start: (u32, u32),
+    end: (u32, u32),
+}
+
+// Five cron fields: minute, hour, day of month, month, day of week.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Cron {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days: Vec<bool>,
+    months: Vec<bool>,
+    weekdays: Vec<bool>,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Schedule {
+    pub fn new(rules: Vec<ScheduleRule>, now: Option<NaiveDateTime>) -> Self {
+        Self {
+            rules,
+            now: now.unwrap_or_else(|| Local::now().naive_local()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    // The first active rule naming the path; `None` when rules name it but
+    // none is active right now.
+    fn active_rule(&self, path: &Path) -> Option<Option<&ScheduleRule>> {
+        let mut named = self.rules.iter().filter(|rule| rule.names(path)).peekable();
+        named.peek()?;
+        Some(named.find(|rule| rule.active_at(self.now)))
+    }
+
+    pub fn admits(&self, path: &Path) -> bool {
+        !matches!(self.active_rule(path), Some(None))
+    }
+
+    pub fn apply(&self, sources: &mut Vec<WeightedSource>) {
+        sources.retain_mut(|source| match self.active_rule(&source.path) {
+            None => true,
+            Some(None) => {
+                debug!(path = %source.path.display(), now = %self.now, "source is off schedule");
+                false
+            }
+            Some(Some(rule)) => {
+                if rule.percent.is_some() {
+                    source.explicit_percent = rule.percent;
+                    source.directory_percent = false;
+                }
+                if rule.weight.is_some() {
+                    source.relative_weight = rule.weight;
+                }
+                true
+            }
+        });
+    }
+}
+
+impl ScheduleRule {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(percent) = self.percent
+            && !(0.0..=100.0).contains(&percent)
+        {
+            return Err(Error::PercentOutOfRange { percent });
+        }
+        if let Some(weight) = self.weight
+            && !(weight.is_finite() && weight > 0.0)
+        {
+            return Err(Error::InvalidWeight {
+                value: weight.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn names(&self, path: &Path) -> bool {
+        path.ends_with(&self.source) || canonical_key(path).ends_with(&self.source)
+    }
+
+    pub fn active_at(&self, now: NaiveDateTime) -> bool {
+        self.dates.is_none_or(|dates| dates.contains(now.date()))
+            && self.cron.as_ref().is_none_or(|cron| cron.matches(now))
+    }
+}
+
+impl DateRange {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        let day = (date.month(), date.day());
+        if self.start <= self.end {
+            self.start <= day && day <= self.end
+        } else {
+            day >= self.start || day <= self.end
+        }
+    }
+}
+
+impl FromStr for DateRange {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || {
+            Error::InvalidArgument(format!(
+                "invalid date range '{value}' (expected MM-DD..MM-DD)"
+            ))
+        };
+        let (start, end) = value.split_once("..").unwrap_or((value, value));
+        let parse = |part: &str| -> Result<(u32, u32)> {
+            let (month, day) = part.trim().split_once('-').ok_or_else(invalid)?;
+            let month: u32 = month.parse().map_err(|_| invalid())?;
+            let day: u32 = day.parse().map_err(|_| invalid())?;
+            // 2000 is a leap year, so 02-29 is accepted.
+            NaiveDate::from_ymd_opt(2000, month, day).ok_or_else(invalid)?;
+            Ok((month, day))
+        };
+        Ok(Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        })
+    }
+}
+
+impl TryFrom<String> for DateRange {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl Cron {
+    pub fn matches(&self, now: NaiveDateTime) -> bool {
+        let day = self.days[now.day() as usize];
+        let weekday = self.weekdays[now.weekday().num_days_from_sunday() as usize];
+        // Like cron, a restricted day of month and day of week match either.
+        let day_ok = match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+        self.minutes[now.minute() as usize]
+            && self.hours[now.hour() as usize]
+            && self.months[now.month() as usize]
+            && day_ok
+    }
+}
+
+impl FromStr for Cron {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(Error::InvalidArgument(format!(
+                "invalid cron '{value}' (expected minute hour day month weekday)"
+            )));
+        };
+        let mut weekdays = cron_field(weekday, 0, 7)?;
+        // Both 0 and 7 mean Sunday.
+        weekdays[0] |= weekdays[7];
+        Ok(Self {
+            minutes: cron_field(minute, 0, 59)?,
+            hours: cron_field(hour, 0, 23)?,
+            days: cron_field(day, 1, 31)?,
+            months: cron_field(month, 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+}
+
+impl TryFrom<String> for Cron {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+// Parses `*`, `N`, `A-B`, and `/STEP` forms joined by commas into a lookup
+// table indexed by the field value.
+fn cron_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>> {
+    let invalid = || Error::InvalidArgument(format!("invalid cron field '{field}' ({min}-{max})"));
+    let mut allowed = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((a, b)) => (
+                    a.parse().map_err(|_| invalid())?,
+                    b.parse().map_err(|_| invalid())?,
+                ),
+                None => {
+                    let n = range.parse().map_err(|_| invalid())?;
+                    (n, if part.contains('/') { max } else { n })
+                }
+            },
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            allowed[value as usize] = true;
+        }
+    }
+    Ok(allowed)
+}
+
+pub fn parse_timestamp(value: &str) -> Result<NaiveDateTime> {
+    TIMESTAMP_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "invalid time '{value}' (expected YYYY-MM-DD or YYYY-MM-DDTHH:MM[:SS])"
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        parse_timestamp(value).expect("timestamp")
+    }
+
+    #[test]
+    fn date_ranges_wrap_and_cron_matches_working_hours() {
+        let december: DateRange = "12-01..12-31".parse().expect("range");
+        assert!(december.contains(at("2026-12-24").date()));
+        assert!(!december.contains(at("2026-11-30").date()));
+        let holidays: DateRange = "12-20..01-06".parse().expect("range");
+        assert!(holidays.contains(at("2027-01-02").date()));
+        assert!(!holidays.contains(at("2027-01-07").date()));
+        assert!("13-01..12-31".parse::<DateRange>().is_err());
+
+        let working: Cron = "* 9-17 * * 1-5".parse().expect("cron");
+        assert!(working.matches(at("2026-10-19T09:30")));
+        assert!(!working.matches(at("2026-10-19T18:00")));
+        assert!(!working.matches(at("2026-10-18T10:00")));
+        let sundays: Cron = "*/15 * * * 7".parse().expect("cron");
+        assert!(sundays.matches(at("2026-10-18T10:45")));
+        assert!(!sundays.matches(at("2026-10-18T10:46")));
+        assert!("* * *".parse::<Cron>().is_err());
+        assert!("60 * * * *".parse::<Cron>().is_err());
+    }
+
+    #[test]
+    fn apply_drops_inactive_sources_and_sets_active_shares() {
+        let rule = |source: &str, cron: &str, percent| ScheduleRule {
+            source: PathBuf::from(source),
+            dates: None,
+            cron: Some(cron.parse().expect("cron")),
+            percent,
+            weight: None,
+        };
+        let source = |path: &str| WeightedSource {
+            path: PathBuf::from(path),
+            explicit_percent: None,
+            relative_weight: None,
+            merged_from: 1,
+            directory_percent: false,
+        };
+        let schedule = Schedule::new(
+            vec![
+                rule("monday", "* * * * 1", Some(20.0)),
+                rule("tech", "* 9-17 * * 1-5", None),
+            ],
+            Some(at("2026-10-19T20:00")),
+        );
+        let mut sources = vec![source("f/monday"), source("f/tech"), source("f/other")];
+        schedule.apply(&mut sources);
+        let paths: Vec<&Path> = sources.iter().map(|s| s.path.as_path()).collect();
+        assert_eq!(paths, [Path::new("f/monday"), Path::new("f/other")]);
+        assert_eq!(sources[0].explicit_percent, Some(20.0));
+        assert!(!schedule.admits(Path::new("f/tech")));
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::{BTreeMap, HashMap};
+
+use tracing::{debug, instrument};
+
+use crate::error::Result;
+use crate::fortune::Fortune;
+use crate::fortune_engine::{FileSelectionMode, LoadedSource, RecordSelection};
+
+const PERCENT_MARKERS: usize = 100;
+const GAMMA_EPSILON: f64 = 1e-14;
+const GAMMA_ITERATIONS: usize = 1000;
+const TINY: f64 = 1e-300;
+const LANCZOS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    pub draws: usize,
+    pub sources: Vec<SourceFrequency>,
+    pub source_test: ChiSquare,
+    pub record_test: ChiSquare,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFrequency {
+    pub name: String,
+    pub expected: f64,
+    pub observed: u64,
+    pub records: Vec<RecordFrequency>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordFrequency {
+    pub index: usize,
+    pub expected: f64,
+    pub observed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChiSquare {
+    pub statistic: f64,
+    pub degrees_of_freedom: usize,
+    pub p_value: f64,
+}
+
+// Draws through the same path as `Fortune::pick` and compares the counts
+// with the shares the selection algorithm should produce, quirks included.
+#[instrument(skip_all, fields(draws))]
+pub fn simulate(fortune: &mut Fortune, draws: usize) -> Result<Simulation> {
+    let source_shares = expected_source_shares(
+        fortune.sources(),
+        fortune.probabilities(),
+        fortune.selection_mode(),
+    );
+
+    let mut sources: Vec<SourceFrequency> = Vec::new();
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+    let mut records: Vec<BTreeMap<usize, RecordFrequency>> = Vec::new();
+    for (entry, share) in fortune.sources().iter().zip(&source_shares) {
+        let name = entry.source.display_name();
+        let slot = *by_name.entry(name.clone()).or_insert_with(|| {
+            sources.push(SourceFrequency {
+                name,
+                expected: 0.0,
+                observed: 0,
+                records: Vec::new(),
+            });
+            records.push(BTreeMap::new());
+            sources.len() - 1
+        });
+        sources[slot].expected += share;
+        for (index, record_share) in expected_record_shares(entry, fortune.record_selection()) {
+            records[slot]
+                .entry(index)
+                .or_insert(RecordFrequency {
+                    index,
+                    expected: 0.0,
+                    observed: 0,
+                })
+                .expected += share * record_share;
+        }
+    }
+
+    for _ in 0..draws {
+        let pick = fortune.pick()?;
+        let slot = match by_name.get(&pick.source_name) {
+            Some(slot) => *slot,
+            None => {
+                sources.push(SourceFrequency {
+                    name: pick.source_name.clone(),
+                    expected: 0.0,
+                    observed: 0,
+                    records: Vec::new(),
+                });
+                records.push(BTreeMap::new());
+                by_name.insert(pick.source_name.clone(), sources.len() - 1);
+                sources.len() - 1
+            }
+        };
+        sources[slot].observed += 1;
+        records[slot]
+            .entry(pick.record_index)
+            .or_insert(RecordFrequency {
+                index: pick.record_index,
+                expected: 0.0,
+                observed: 0,
+            })
+            .observed += 1;
+    }
+
+    for (source, records) in sources.iter_mut().zip(records) {
+        source.records = records.into_values().collect();
+    }
+    let source_test = chi_square(
+        sources
+            .iter()
+            .map(|source| (source.observed, source.expected)),
+        draws,
+    );
+    let record_test = chi_square(
+        sources
+            .iter()
+            .flat_map(|source| &source.records)
+            .map(|record| (record.observed, record.expected)),
+        draws,
+    );
+    debug!(
+        source_statistic = source_test.statistic,
+        record_statistic = record_test.statistic,
+        "simulation finished"
+    );
+    Ok(Simulation {
+        draws,
+        sources,
+        source_test,
+        record_test,
+    })
+}
+
+pub fn expected_source_shares(
+    entries: &[LoadedSource],
+    probabilities: &[f64],
+    mode: FileSelectionMode,
+) -> Vec<f64> {
+    let mut shares = vec![0.0; entries.len()];
+    if entries.is_empty() {
+        return shares;
+    }
+    match mode {
+        // Mirrors choose_source_index: an integer marker in 0..100 walks the
+        // percentages, and anything left over lands on the last source.
+        FileSelectionMode::ProbabilityPercent => {
+            for marker in 0..PERCENT_MARKERS {
+                let mut marker = marker as f64;
+                let mut picked = entries.len() - 1;
+                for (idx, probability) in probabilities.iter().enumerate() {
+                    if marker < *probability {
+                        picked = idx;
+                        break;
+                    }
+                    marker -= *probability;
+                }
+                shares[picked] += 1.0 / PERCENT_MARKERS as f64;
+            }
+        }
+        FileSelectionMode::WeightedDraw => {
+            let total: f64 = probabilities.iter().sum();
+            for (share, probability) in shares.iter_mut().zip(probabilities) {
+                *share = probability / total;
+            }
+        }
+        FileSelectionMode::CandidateCount => {
+            let total: usize = entries.iter().map(|e| e.candidate_indices.len()).sum();
+            for (share, entry) in shares.iter_mut().zip(entries) {
+                *share = entry.candidate_indices.len() as f64 / total.max(1) as f64;
+            }
+        }
+    }
+    shares
+}
+
+// Mirrors select_record: in parity mode the start position is bumped by one
+// (get_pos) and then walks forward to the next candidate, so records after a
+// run of filtered-out ones are picked more often.
+pub fn expected_record_shares(entry: &LoadedSource, records: RecordSelection) -> Vec<(usize, f64)> {
+    let total = entry.source.record_count();
+    if total == 0 || entry.candidate_indices.is_empty() {
+        return Vec::new();
+    }
+    if records == RecordSelection::Fair {
+        let share = 1.0 / entry.candidate_indices.len() as f64;
+        return entry
+            .candidate_indices
+            .iter()
+            .map(|idx| (*idx, share))
+            .collect();
+    }
+    let mut is_candidate = vec![false; total];
+    for idx in &entry.candidate_indices {
+        if let Some(slot) = is_candidate.get_mut(*idx) {
+            *slot = true;
+        }
+    }
+    let mut next = vec![0usize; total];
+    // The second pass fixes up positions that wrap past the last candidate.
+    let mut upcoming = 0;
+    for _ in 0..2 {
+        for idx in (0..total).rev() {
+            if is_candidate[idx] {
+                upcoming = idx;
+            }
+            next[idx] = upcoming;
+        }
+    }
+    let mut hits: BTreeMap<usize, usize> = BTreeMap::new();
+    for start in 0..total {
+        *hits.entry(next[(start + 1) % total]).or_default() += 1;
+    }
+    hits.into_iter()
+        .map(|(idx, count)| (idx, count as f64 / total as f64))
+        .collect()
+}
+
+pub fn chi_square(cells: impl Iterator<Item = (u64, f64)>, draws: usize) -> ChiSquare {
+    let mut statistic = 0.0;
+    let mut categories = 0usize;
+    for (observed, share) in cells {
+        let expected = share * draws as f64;
+        if expected > 0.0 {
+            statistic += (observed as f64 - expected).powi(2) / expected;
+            categories += 1;
+        } else if observed > 0 {
+            statistic = f64::INFINITY;
+        }
+    }
+    let degrees_of_freedom = categories.saturating_sub(1);
+    let p_value = if degrees_of_freedom == 0 {
+        if statistic.is_infinite() { 0.0 } else { 1.0 }
+    } else {
+        gamma_q(degrees_of_freedom as f64 / 2.0, statistic / 2.0)
+    };
+    ChiSquare {
+        statistic,
+        degrees_of_freedom,
+        p_value,
+    }
+}
+
+fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    if x.is_infinite() {
+        return 0.0;
+    }
+    let prefactor = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        let mut ap = a;
+        for _ in 0..GAMMA_ITERATIONS {
+            ap += 1.0;
+            term *= x / ap;
+            sum += term;
+            if term.abs() < sum.abs() * GAMMA_EPSILON {
+                break;
+            }
+        }
+        (1.0 - sum * prefactor).clamp(0.0, 1.0)
+    } else {
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..GAMMA_ITERATIONS {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = b + an / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < GAMMA_EPSILON {
+                break;
+            }
+        }
+        (prefactor * h).clamp(0.0, 1.0)
+    }
+}
+
+fn ln_gamma(x: f64) -> f64 {
+    if x < 0.5 {
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = LANCZOS[1..]
+        .iter()
+        .enumerate()
+        .fold(LANCZOS[0], |acc, (i, coeff)| {
+            acc + coeff / (x + i as f64 + 1.0)
+        });
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::datfile::LengthFilter;
+    use crate::fortune_source::MemorySource;
+
+    #[test]
+    fn expected_shares_model_percent_markers_and_walk_forward() {
+        let source = Arc::new(MemorySource::new("s", ["a", "bb", "c", "dd"]));
+        let mut entry = LoadedSource::new(source, None, LengthFilter::Any).expect("load");
+        entry.candidate_indices = vec![0, 1];
+        assert_eq!(
+            expected_record_shares(&entry, RecordSelection::Parity),
+            vec![(0, 0.75), (1, 0.25)]
+        );
+        assert_eq!(
+            expected_record_shares(&entry, RecordSelection::Fair),
+            vec![(0, 0.5), (1, 0.5)]
+        );
+
+        let entries = [entry.clone(), entry];
+        let shares = expected_source_shares(
+            &entries,
+            &[33.3, 66.7],
+            FileSelectionMode::ProbabilityPercent,
+        );
+        assert!((shares[0] - 0.34).abs() < 1e-9, "{shares:?}");
+        assert!((shares[1] - 0.66).abs() < 1e-9, "{shares:?}");
+
+        let shares =
+            expected_source_shares(&entries, &[99.5, 0.5], FileSelectionMode::WeightedDraw);
+        assert!((shares[1] - 0.005).abs() < 1e-9, "{shares:?}");
+    }
+
+    #[test]
+    fn chi_square_matches_known_p_values() {
+        let test = chi_square([(60, 0.5), (40, 0.5)].into_iter(), 100);
+        assert!((test.statistic - 4.0).abs() < 1e-9);
+        assert_eq!(test.degrees_of_freedom, 1);
+        assert!((test.p_value - 0.0455).abs() < 1e-3, "{test:?}");
+
+        let three = chi_square([(30, 0.25), (20, 0.25), (50, 0.5)].into_iter(), 100);
+        assert!((three.p_value - (-1.0f64).exp()).abs() < 1e-9, "{three:?}");
+
+        let impossible = chi_square([(5, 1.0), (1, 0.0)].into_iter(), 6);
+        assert_eq!(impossible.p_value, 0.0);
+    }
+}
@@ -1,18 +1,32 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Result, bail};
 use tracing::{debug, instrument};
 
+use crate::error::{Error, Result};
+
+pub const TAG_PREFIX: &str = "tag:";
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct SourceSpec {
     pub path: PathBuf,
     pub percent: Option<f64>,
+    pub weight: Option<f64>,
+}
+
+impl SourceSpec {
+    pub fn tag(&self) -> Option<&str> {
+        self.path.to_str()?.strip_prefix(TAG_PREFIX)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct WeightedSource {
     pub path: PathBuf,
     pub explicit_percent: Option<f64>,
+    pub relative_weight: Option<f64>,
+    pub merged_from: usize,
+    // The percentage was spread from a directory or `all` spec.
+    pub directory_percent: bool,
 }
 
 #[instrument(skip_all)]
@@ -23,12 +37,13 @@ pub fn parse_source_specs(args: &[String]) -> Result<Vec<SourceSpec>> {
         let token = &args[i];
         if let Some((pct, path_part)) = parse_percent_prefix(token)? {
             if path_part.is_empty() {
-                let next = args.get(i + 1).ok_or_else(|| {
-                    anyhow::anyhow!("missing path after percentage token '{token}'")
+                let next = args.get(i + 1).ok_or_else(|| Error::MissingPercentPath {
+                    token: token.clone(),
                 })?;
                 parsed.push(SourceSpec {
                     path: PathBuf::from(next),
                     percent: Some(pct),
+                    weight: None,
                 });
                 i += 2;
                 continue;
@@ -36,25 +51,50 @@ pub fn parse_source_specs(args: &[String]) -> Result<Vec<SourceSpec>> {
             parsed.push(SourceSpec {
                 path: PathBuf::from(path_part),
                 percent: Some(pct),
+                weight: None,
+            });
+        } else if let Some((weight, path_part)) = parse_weight_prefix(token)? {
+            parsed.push(SourceSpec {
+                path: PathBuf::from(path_part),
+                percent: None,
+                weight: Some(weight),
             });
         } else {
             parsed.push(SourceSpec {
                 path: PathBuf::from(token),
                 percent: None,
+                weight: None,
             });
         }
         i += 1;
     }
 
-    let total_specified: f64 = parsed.iter().filter_map(|p| p.percent).sum();
+    // Percentages nested inside a directory or `all` in the same list are
+    // folded into their parents during discovery, which checks those; the
+    // specs nothing else can contain must fit in 100% on their own.
+    let total_specified: f64 = parsed
+        .iter()
+        .filter(|spec| !may_nest(spec, &parsed))
+        .filter_map(|spec| spec.percent)
+        .sum();
     if total_specified > 100.0 + f64::EPSILON {
-        bail!("specified percentages exceed 100% (got {total_specified:.3}%)");
+        return Err(Error::PercentagesExceed100 {
+            total: total_specified,
+        });
     }
 
     debug!(count = parsed.len(), total_specified, "parsed source specs");
     Ok(parsed)
 }
 
+fn may_nest(spec: &SourceSpec, specs: &[SourceSpec]) -> bool {
+    specs.iter().any(|parent| {
+        parent.path != spec.path
+            && (parent.path == Path::new("all")
+                || (parent.path.is_dir() && spec.path.starts_with(&parent.path)))
+    })
+}
+
 fn parse_percent_prefix(token: &str) -> Result<Option<(f64, &str)>> {
     if let Some(idx) = token.find('%') {
         let (lhs, rhs) = token.split_at(idx);
@@ -62,11 +102,11 @@ fn parse_percent_prefix(token: &str) -> Result<Option<(f64, &str)>> {
             return Ok(None);
         }
         if lhs.chars().all(|c| c.is_ascii_digit() || c == '.') {
-            let pct: f64 = lhs
-                .parse()
-                .map_err(|_| anyhow::anyhow!("invalid percentage value '{lhs}'"))?;
+            let pct: f64 = lhs.parse().map_err(|_| Error::InvalidPercent {
+                value: lhs.to_string(),
+            })?;
             if !(0.0..=100.0).contains(&pct) {
-                bail!("percentage out of range 0..=100: {pct}");
+                return Err(Error::PercentOutOfRange { percent: pct });
             }
             return Ok(Some((pct, rhs.trim_start_matches('%'))));
         }
@@ -74,6 +114,31 @@ fn parse_percent_prefix(token: &str) -> Result<Option<(f64, &str)>> {
     Ok(None)
 }
 
+// `3:linux` gives a source a relative weight; unlike percentages these
+// need not add up to anything.
+fn parse_weight_prefix(token: &str) -> Result<Option<(f64, &str)>> {
+    let Some((lhs, rhs)) = token.split_once(':') else {
+        return Ok(None);
+    };
+    if lhs.is_empty() || !lhs.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return Ok(None);
+    }
+    let weight: f64 = lhs.parse().map_err(|_| Error::InvalidWeight {
+        value: lhs.to_string(),
+    })?;
+    if !weight.is_finite() || weight <= 0.0 {
+        return Err(Error::InvalidWeight {
+            value: lhs.to_string(),
+        });
+    }
+    if rhs.is_empty() {
+        return Err(Error::InvalidArgument(format!(
+            "missing path after weight token '{token}'"
+        )));
+    }
+    Ok(Some((weight, rhs)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -94,4 +159,37 @@ mod tests {
         assert_eq!(parsed[0].percent, Some(25.0));
         assert_eq!(parsed[0].path, PathBuf::from("foo"));
     }
+
+    #[test]
+    fn parse_relative_weights() {
+        let args = vec!["3:linux".to_string(), "0.5:tag:zippy".to_string()];
+        let parsed = parse_source_specs(&args).expect("parse");
+        assert_eq!(parsed[0].weight, Some(3.0));
+        assert_eq!(parsed[0].path, PathBuf::from("linux"));
+        assert_eq!(parsed[1].weight, Some(0.5));
+        assert_eq!(parsed[1].tag(), Some("zippy"));
+        assert!(parse_source_specs(&["0:linux".to_string()]).is_err());
+    }
+
+    #[test]
+    fn only_nested_percentages_defer_the_total_check() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let dir = tmp.path().join("fortunes");
+        std::fs::create_dir(&dir).expect("create dir");
+        let spec = |path: &Path| path.display().to_string();
+        let args = |specs: &[(&str, String)]| {
+            specs
+                .iter()
+                .flat_map(|(pct, path)| [pct.to_string(), path.clone()])
+                .collect::<Vec<_>>()
+        };
+
+        let nested = args(&[("60%", spec(&dir)), ("60%", spec(&dir.join("linux")))]);
+        assert!(parse_source_specs(&nested).is_ok());
+        let side_by_side = args(&[("60%", spec(&tmp.path().join("a"))), ("60%", spec(&dir))]);
+        assert!(matches!(
+            parse_source_specs(&side_by_side),
+            Err(Error::PercentagesExceed100 { .. })
+        ));
+    }
 }
@@ -1,7 +1,13 @@
-use anyhow::{Result, bail};
+use std::fs;
+use std::path::Path;
+
 use tracing::{debug, instrument};
 
-use crate::datfile::{DatFile, DatHeader, RecordSpan, STR_ORDERED, STR_RANDOM, STRFILE_VERSION};
+use crate::datfile::{
+    DatFile, DatHeader, RecordSpan, STR_COMMENTS, STR_ORDERED, STR_RANDOM, STR_ROTATED,
+    STRFILE_VERSION, dat_path_for_text,
+};
+use crate::error::{Error, Result};
 use crate::rng::FortuneRng;
 
 #[derive(Debug, Clone, Copy)]
@@ -75,12 +81,12 @@ pub fn parse_record_spans(input: &[u8], delimiter: u8, allow_empty: bool) -> Vec
 #[instrument(skip_all)]
 pub fn build_dat_from_text(input: &[u8], opts: BuildOptions) -> Result<(DatFile, BuildStats)> {
     if opts.order_offsets && opts.randomize_offsets {
-        bail!("--order and --random cannot be used together");
+        return Err(Error::ConflictingOrder);
     }
 
     let spans = parse_record_spans(input, opts.delimiter, opts.allow_empty);
     if spans.is_empty() {
-        bail!("no fortune records were parsed");
+        return Err(Error::EmptyCorpus);
     }
 
     let shortest = spans.iter().map(len_for_span).min().unwrap_or(0);
@@ -96,9 +102,8 @@ pub fn build_dat_from_text(input: &[u8], opts: BuildOptions) -> Result<(DatFile,
 
     let mut offsets = Vec::with_capacity(ordered.len());
     for span in &ordered {
-        let start = u32::try_from(span.start).map_err(|_| {
-            anyhow::anyhow!("record start offset {} exceeds STRFILE u32", span.start)
-        })?;
+        let start =
+            u32::try_from(span.start).map_err(|_| Error::OffsetOverflow { offset: span.start })?;
         offsets.push(start);
     }
 
@@ -127,6 +132,66 @@ pub fn build_dat_from_text(input: &[u8], opts: BuildOptions) -> Result<(DatFile,
     Ok((dat, stats))
 }
 
+pub fn parse_delimiter(value: &str) -> Result<u8> {
+    let bytes = value.as_bytes();
+    if bytes.len() != 1 {
+        return Err(Error::InvalidArgument(format!(
+            "delimiter must be a single byte, got '{value}'"
+        )));
+    }
+    Ok(bytes[0])
+}
+
+pub fn render_corpus<S: AsRef<[u8]>>(records: &[S], delimiter: u8) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (idx, record) in records.iter().enumerate() {
+        if idx > 0 {
+            out.push(delimiter);
+            out.push(b'\n');
+        }
+        let record = record.as_ref();
+        out.extend_from_slice(record);
+        if !record.ends_with(b"\n") {
+            out.push(b'\n');
+        }
+    }
+    out
+}
+
+#[instrument(skip_all, fields(path = %text_path.display()))]
+pub fn write_indexed_corpus(
+    text_path: &Path,
+    text: &[u8],
+    opts: BuildOptions,
+) -> Result<BuildStats> {
+    let (dat, stats) = build_dat_from_text(text, opts)?;
+    fs::write(text_path, text).map_err(Error::io("writing", text_path))?;
+    dat.write_to_path(&dat_path_for_text(text_path))?;
+    Ok(stats)
+}
+
+// Rewrites a corpus that already has an index with the same delimiter and
+// header flags. The builder only knows ordering, so rot13 and comment flags
+// are carried over to keep the corpus decoding the same way.
+#[instrument(skip_all, fields(path = %text_path.display()))]
+pub fn rewrite_indexed_corpus(
+    text_path: &Path,
+    text: &[u8],
+    previous: &DatHeader,
+) -> Result<BuildStats> {
+    let opts = BuildOptions {
+        delimiter: previous.delim,
+        randomize_offsets: previous.flags & STR_RANDOM != 0,
+        order_offsets: previous.flags & STR_ORDERED != 0,
+        allow_empty: false,
+    };
+    let (mut dat, stats) = build_dat_from_text(text, opts)?;
+    dat.header.flags |= previous.flags & (STR_ROTATED | STR_COMMENTS);
+    fs::write(text_path, text).map_err(Error::io("writing", text_path))?;
+    dat.write_to_path(&dat_path_for_text(text_path))?;
+    Ok(stats)
+}
+
 fn fisher_yates_shuffle(items: &mut [RecordSpan], rng: &mut FortuneRng) {
     if items.len() < 2 {
         return;
@@ -169,4 +234,11 @@ mod tests {
         assert_eq!(stats.record_count, 2);
         assert_eq!(dat.offsets, vec![0, 8]);
     }
+
+    #[test]
+    fn rendered_corpus_parses_back_into_records() {
+        let text = render_corpus(&["one", "two\n", "three\nlines"], b'%');
+        assert_eq!(text, b"one\n%\ntwo\n%\nthree\nlines\n");
+        assert_eq!(parse_record_spans(&text, b'%', false).len(), 3);
+    }
 }
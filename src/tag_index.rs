@@ -0,0 +1,150 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, instrument, trace};
+
+use crate::error::{Error, Result};
+use crate::fortune_engine::LoadedSource;
+use crate::fortune_source::{FortuneSource, SourceKind, SourceMetadata};
+use crate::record::{RecordMetadata, normalize_tag};
+use crate::sources::TAG_PREFIX;
+
+const TAG_INDEX_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagIndex {
+    version: u32,
+    pub tags: BTreeMap<String, Vec<usize>>,
+}
+
+impl TagIndex {
+    pub fn build(source: &dyn FortuneSource) -> Result<Self> {
+        let mut tags: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for idx in 0..source.record_count() {
+            let meta = RecordMetadata::parse(&source.record_text_lossy(idx)?);
+            for tag in meta.tags {
+                tags.entry(tag).or_default().push(idx);
+            }
+        }
+        Ok(Self {
+            version: TAG_INDEX_VERSION,
+            tags,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    pub fn records(&self, tag: &str) -> &[usize] {
+        self.tags.get(tag).map_or(&[], Vec::as_slice)
+    }
+
+    #[instrument(skip_all, fields(path = %path.display()))]
+    pub fn write_to_path(&self, path: &Path) -> Result<()> {
+        let encoded = serde_json::to_vec(self)
+            .map_err(|err| Error::InvalidArgument(format!("cannot encode tag index: {err}")))?;
+        fs::write(path, encoded).map_err(Error::io("writing tag index", path))
+    }
+
+    pub fn read_fresh(text_path: &Path) -> Option<Self> {
+        let path = tag_path_for_text(text_path);
+        let index_modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok()?;
+        let text_modified = fs::metadata(text_path)
+            .and_then(|meta| meta.modified())
+            .ok()?;
+        if index_modified < text_modified {
+            debug!(path = %path.display(), "ignoring stale tag index");
+            return None;
+        }
+        let bytes = fs::read(&path).ok()?;
+        match serde_json::from_slice::<Self>(&bytes) {
+            Ok(index) if index.version == TAG_INDEX_VERSION => {
+                trace!(path = %path.display(), tags = index.tags.len(), "loaded tag index");
+                Some(index)
+            }
+            _ => {
+                debug!(path = %path.display(), "ignoring unreadable tag index");
+                None
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TaggedSource {
+    tag: String,
+    records: Vec<(Arc<dyn FortuneSource>, usize)>,
+}
+
+impl TaggedSource {
+    pub fn collect(tag: &str, sources: &[LoadedSource]) -> Result<Self> {
+        let tag = normalize_tag(tag);
+        let mut records = Vec::new();
+        for loaded in sources {
+            for idx in loaded.tagged_indices(&tag)? {
+                records.push((Arc::clone(&loaded.source), idx));
+            }
+        }
+        debug!(tag = %tag, records = records.len(), "collected tagged records");
+        Ok(Self { tag, records })
+    }
+}
+
+impl FortuneSource for TaggedSource {
+    fn display_name(&self) -> String {
+        format!("{TAG_PREFIX}{}", self.tag)
+    }
+
+    fn record_count(&self) -> usize {
+        self.records.len()
+    }
+
+    fn record_bytes(&self, index: usize) -> Result<Cow<'_, [u8]>> {
+        let (source, idx) = self
+            .records
+            .get(index)
+            .ok_or(Error::RecordOutOfRange { index })?;
+        source.record_bytes(*idx)
+    }
+
+    fn origin(&self, index: usize) -> Option<(&dyn FortuneSource, usize)> {
+        self.records
+            .get(index)
+            .map(|(source, idx)| (source.as_ref(), *idx))
+    }
+
+    fn metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            kind: SourceKind::Tagged,
+            path: None,
+            offensive: false,
+        }
+    }
+}
+
+pub fn tag_path_for_text(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.tags", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fortune_source::MemorySource;
+
+    #[test]
+    fn index_maps_tags_to_record_indices() {
+        let source = MemorySource::new(
+            "tagged",
+            ["#tags: a, b\none", "two", "#tags: b\n#tags: b\nthree"],
+        );
+        let index = TagIndex::build(&source).expect("build");
+        assert_eq!(index.records("a"), &[0]);
+        assert_eq!(index.records("b"), &[0, 2]);
+        assert!(index.records("missing").is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+use std::fs;
+use std::process::Command;
+
+use rustune::datfile::{DatFile, STR_RANDOM, dat_path_for_text};
+use tempfile::tempdir;
+
+#[test]
+fn imported_yaml_corpus_is_readable_by_rustune() {
+    let tmp = tempdir().expect("tempdir");
+    let input = tmp.path().join("quotes.yaml");
+    fs::write(
+        &input,
+        "- text: Measure twice.\n  author: A Carpenter\n- Cut once.\n",
+    )
+    .expect("write yaml");
+    let output = tmp.path().join("quotes");
+
+    let status = Command::new(env!("CARGO_BIN_EXE_fortune-import"))
+        .arg("-s")
+        .arg(&input)
+        .arg(&output)
+        .status()
+        .expect("run fortune-import");
+    assert!(status.success());
+    assert_eq!(
+        fs::read_to_string(&output).expect("read corpus"),
+        "Measure twice.\n\t\t-- A Carpenter\n%\nCut once.\n"
+    );
+
+    let matched = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .arg("-m")
+        .arg("Carpenter")
+        .arg(&output)
+        .output()
+        .expect("run fortune");
+    assert!(matched.status.success());
+    assert_eq!(
+        String::from_utf8(matched.stdout).expect("stdout"),
+        "Measure twice.\n\t\t-- A Carpenter\n%\n"
+    );
+}
+
+#[test]
+fn export_emits_one_ndjson_line_per_record() {
+    let tmp = tempdir().expect("tempdir");
+    let input = tmp.path().join("quotes.txt");
+    fs::write(&input, "first\nsecond\n").expect("write lines");
+    let corpus = tmp.path().join("quotes");
+    let status = Command::new(env!("CARGO_BIN_EXE_fortune-import"))
+        .arg("-s")
+        .arg(&input)
+        .arg(&corpus)
+        .status()
+        .expect("run fortune-import");
+    assert!(status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fortune-export"))
+        .arg(&corpus)
+        .output()
+        .expect("run fortune-export");
+    assert!(output.status.success());
+    let source = corpus.display().to_string();
+    let expected = format!(
+        "{{\"index\":0,\"source\":{source:?},\"length\":6,\"offset\":0,\"text\":\"first\\n\"}}\n\
+         {{\"index\":1,\"source\":{source:?},\"length\":7,\"offset\":8,\"text\":\"second\\n\"}}\n"
+    );
+    assert_eq!(String::from_utf8(output.stdout).expect("stdout"), expected);
+}
+
+#[test]
+fn lint_reports_rustc_style_locations_and_fixes_safe_issues() {
+    let tmp = tempdir().expect("tempdir");
+    let corpus = tmp.path().join("notes");
+    fs::write(&corpus, "tidy  \n%\n%\nclean").expect("write corpus");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fortune-lint"))
+        .arg(&corpus)
+        .output()
+        .expect("run fortune-lint");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).expect("stderr");
+    assert!(stderr.contains(&format!(
+        "warning[trailing-whitespace]: trailing whitespace\n --> {}:1:5\n  |\n1 | tidy  \n  |     ^^\n",
+        corpus.display()
+    )));
+    assert!(stderr.contains("warning[empty-record]"));
+    assert!(stderr.contains("warning[missing-final-newline]"));
+
+    let fixed = Command::new(env!("CARGO_BIN_EXE_fortune-lint"))
+        .arg("--fix")
+        .arg(&corpus)
+        .output()
+        .expect("run fortune-lint --fix");
+    assert!(fixed.status.success(), "{fixed:?}");
+    assert_eq!(
+        fs::read_to_string(&corpus).expect("read fixed"),
+        "tidy\n%\nclean\n"
+    );
+}
+
+#[test]
+fn lint_fix_keeps_the_existing_index_options() {
+    let tmp = tempdir().expect("tempdir");
+    let corpus = tmp.path().join("shuffled");
+    fs::write(&corpus, "tidy  \n#\nclean\n").expect("write corpus");
+    let status = Command::new(env!("CARGO_BIN_EXE_strfile"))
+        .args(["-s", "-r", "-c", "#"])
+        .arg(&corpus)
+        .status()
+        .expect("run strfile");
+    assert!(status.success());
+
+    let fixed = Command::new(env!("CARGO_BIN_EXE_fortune-lint"))
+        .arg("--fix")
+        .arg(&corpus)
+        .output()
+        .expect("run fortune-lint --fix");
+    assert!(fixed.status.success(), "{fixed:?}");
+    assert_eq!(
+        fs::read_to_string(&corpus).expect("read fixed"),
+        "tidy\n#\nclean\n"
+    );
+    let dat = DatFile::read_from_path(&dat_path_for_text(&corpus)).expect("read dat");
+    assert_eq!(dat.header.flags, STR_RANDOM);
+    assert_eq!(dat.header.delim, b'#');
+    assert_eq!(dat.header.numstr, 2);
+}
+
+#[test]
+fn dedupe_rewrites_later_duplicates_and_regenerates_dat() {
+    let tmp = tempdir().expect("tempdir");
+    let first = tmp.path().join("first");
+    let second = tmp.path().join("second");
+    fs::write(&first, "Keep it simple.\n%\nShip it.\n").expect("write first");
+    fs::write(&second, "keep it   SIMPLE!\n%\nMeasure twice.\n").expect("write second");
+    for path in [&first, &second] {
+        let status = Command::new(env!("CARGO_BIN_EXE_strfile"))
+            .arg("-s")
+            .arg(path)
+            .status()
+            .expect("run strfile");
+        assert!(status.success());
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fortune-dedupe"))
+        .arg("--rewrite")
+        .arg(&first)
+        .arg(&second)
+        .output()
+        .expect("run fortune-dedupe");
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).expect("stdout");
+    assert!(stdout.contains(&format!(
+        "duplicates of {} #0: Keep it simple.\n  exact {} #0: keep it   SIMPLE!\n",
+        first.display(),
+        second.display()
+    )));
+    assert!(stdout.contains("1 duplicate record(s) in 1 group(s)\n"));
+    assert_eq!(
+        fs::read_to_string(&second).expect("read second"),
+        "Measure twice.\n"
+    );
+
+    let listing = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .arg("-m")
+        .arg(".")
+        .arg(&second)
+        .output()
+        .expect("run fortune");
+    assert_eq!(
+        String::from_utf8(listing.stdout).expect("stdout"),
+        "Measure twice.\n%\n"
+    );
+}
+
+#[test]
+fn corpus_merge_and_split_handle_custom_delimiters() {
+    let tmp = tempdir().expect("tempdir");
+    let hashed = tmp.path().join("hashed");
+    let plain = tmp.path().join("plain");
+    fs::write(&hashed, "Use 100% effort.\n#\nRust is fun.\n").expect("write hashed");
+    fs::write(&plain, "rust is FUN\n%\nA longer fortune than the rest.\n").expect("write plain");
+    let status = Command::new(env!("CARGO_BIN_EXE_strfile"))
+        .args(["-s", "-c", "#"])
+        .arg(&hashed)
+        .status()
+        .expect("run strfile");
+    assert!(status.success());
+
+    let merged = tmp.path().join("merged");
+    let output = Command::new(env!("CARGO_BIN_EXE_fortune-corpus"))
+        .arg("merge")
+        .arg("-O")
+        .arg(&merged)
+        .arg(&hashed)
+        .arg(&plain)
+        .output()
+        .expect("run fortune-corpus merge");
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        fs::read_to_string(&merged).expect("read merged"),
+        "Use 100% effort.\n%\nRust is fun.\n%\nA longer fortune than the rest.\n"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fortune-corpus"))
+        .args(["split", "--lengths", "15"])
+        .arg(&merged)
+        .output()
+        .expect("run fortune-corpus split");
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        fs::read_to_string(tmp.path().join("merged-le15")).expect("read short"),
+        "Rust is fun.\n"
+    );
+    assert!(tmp.path().join("merged-gt15.dat").exists());
+
+    for rules in [["a=Rust", "a=longer"], ["a=Rust", "rest=longer"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_fortune-corpus"))
+            .args(["split", "--regex", rules[0], "--regex", rules[1]])
+            .arg(&merged)
+            .output()
+            .expect("run fortune-corpus split");
+        assert_eq!(output.status.code(), Some(2), "{output:?}");
+        assert!(!tmp.path().join("merged-a").exists());
+    }
+}
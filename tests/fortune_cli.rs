@@ -46,6 +46,232 @@ fn list_files_writes_formatted_probabilities_to_stderr() {
     assert_eq!(stderr, expected);
 }
 
+#[test]
+fn relative_weights_and_weighting_strategies_set_file_shares() {
+    let tmp = tempdir().expect("tempdir");
+    let big = tmp.path().join("big");
+    let small = tmp.path().join("small");
+    write_indexed_file(&big, b"a\n%\nb\n%\nc\n%\nd\n");
+    let third = tmp.path().join("third");
+    write_indexed_file(&small, b"a much longer record\n");
+    write_indexed_file(&third, b"x\n");
+
+    let shares = |args: &[String]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+            .arg("-f")
+            .args(args)
+            .output()
+            .expect("run fortune -f");
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stderr)
+            .expect("stderr")
+            .lines()
+            .map(|line| line.split_whitespace().next().unwrap_or("").to_string())
+            .collect::<Vec<_>>()
+    };
+    let big = big.display().to_string();
+    let small = small.display().to_string();
+    assert_eq!(
+        shares(&[format!("1:{big}"), format!("3:{small}")]),
+        ["25.00%", "75.00%"]
+    );
+    assert_eq!(
+        shares(&[
+            format!("3:{big}"),
+            small.clone(),
+            "20%".into(),
+            third.display().to_string()
+        ]),
+        ["60.00%", "20.00%", "20.00%"]
+    );
+    assert_eq!(
+        shares(&[
+            "--weighting".into(),
+            "sqrt".into(),
+            big.clone(),
+            small.clone()
+        ]),
+        ["66.67%", "33.33%"]
+    );
+    assert_eq!(
+        shares(&["--weighting".into(), "bytes".into(), big, small]),
+        ["27.59%", "72.41%"]
+    );
+}
+
+#[test]
+fn nested_percentages_and_all_share_their_parent_budget() {
+    let tmp = tempdir().expect("tempdir");
+    let dir = tmp.path().join("dir");
+    fs::create_dir(&dir).expect("mkdir");
+    for name in ["a", "b", "c"] {
+        write_indexed_file(
+            &dir.join(name),
+            format!("{name}\n%\n{name}{name}\n").as_bytes(),
+        );
+    }
+    let other = tmp.path().join("other");
+    write_indexed_file(&other, b"other\n");
+
+    let list = |fortune_path: &std::path::Path, args: &[String]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+            .env("FORTUNE_PATH", fortune_path)
+            .arg("-f")
+            .args(args)
+            .output()
+            .expect("run fortune -f");
+        (
+            output.status.code(),
+            String::from_utf8(output.stderr).expect("stderr"),
+        )
+    };
+    let dir_arg = dir.display().to_string();
+    let child = dir.join("a").display().to_string();
+    let other_arg = other.display().to_string();
+    let canon = |path: &std::path::Path| fs::canonicalize(path).expect("canonicalize");
+
+    let (code, stderr) = list(
+        &dir,
+        &[
+            "60%".into(),
+            dir_arg.clone(),
+            "30%".into(),
+            child.clone(),
+            other_arg.clone(),
+        ],
+    );
+    assert_eq!(code, Some(0), "{stderr}");
+    assert_eq!(
+        stderr,
+        format!(
+            "15.00% {}\n15.00% {}\n30.00% {}\n40.00% {}\n",
+            canon(&dir.join("b")).display(),
+            canon(&dir.join("c")).display(),
+            canon(&dir.join("a")).display(),
+            canon(&other).display()
+        )
+    );
+
+    let (code, stderr) = list(&dir, &["20%".into(), dir_arg, "30%".into(), child]);
+    assert_eq!(code, Some(2), "{stderr}");
+
+    let (_, stderr) = list(&dir, &["30%".into(), "all".into()]);
+    assert!(
+        stderr.lines().all(|line| line.starts_with("33.33% ")),
+        "{stderr}"
+    );
+
+    let (_, stderr) = list(&dir, &[other_arg.clone(), other_arg]);
+    let listed = format!("100.00% {}", canon(&other).display());
+    assert!(
+        stderr.lines().count() == 1 && stderr.starts_with(&listed),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn overlapping_specs_merge_by_policy_and_warn() {
+    let tmp = tempdir().expect("tempdir");
+    let dir = tmp.path().join("dir");
+    fs::create_dir(&dir).expect("mkdir");
+    write_indexed_file(&dir.join("a"), b"a\n%\naa\n");
+    write_indexed_file(&dir.join("b"), b"b\n%\nbb\n");
+    let single = tmp.path().join("single");
+    write_indexed_file(&single, b"single\n");
+    let config = tmp.path().join("config.toml");
+    fs::write(&config, "[selection]\noverlap = \"sum\"\n").expect("write config");
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+            .args(args)
+            .arg(&dir)
+            .arg(dir.join("a"))
+            .arg("30%")
+            .arg(&single)
+            .arg("20%")
+            .arg(&single)
+            .output()
+            .expect("run fortune");
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stderr).expect("stderr")
+    };
+    let canon = |path: &std::path::Path| fs::canonicalize(path).expect("canonicalize");
+
+    let listed = run(&["-f"]);
+    assert_eq!(
+        listed,
+        format!(
+            "35.00% {} (merged from 2 specs)\n35.00% {}\n30.00% {} (merged from 2 specs)\n",
+            canon(&dir.join("a")).display(),
+            canon(&dir.join("b")).display(),
+            canon(&single).display()
+        )
+    );
+    let summed = run(&["--config", config.to_str().expect("utf8 path"), "-f"]);
+    assert!(
+        summed.ends_with(&format!(
+            "50.00% {} (merged from 2 specs)\n",
+            canon(&single).display()
+        )),
+        "{summed}"
+    );
+
+    let picked = run(&[]);
+    assert!(
+        picked.contains("is named by 2 source specs; merged with the explicit policy"),
+        "{picked}"
+    );
+}
+
+#[test]
+fn schedule_rules_follow_the_now_override() {
+    let tmp = tempdir().expect("tempdir");
+    let dir = tmp.path().join("fortunes");
+    fs::create_dir(&dir).expect("mkdir");
+    for name in ["holidays", "monday", "tech", "other"] {
+        write_indexed_file(&dir.join(name), format!("{name}\n").as_bytes());
+    }
+    let config = tmp.path().join("config.toml");
+    fs::write(
+        &config,
+        "[[schedule]]\nsource = \"holidays\"\ndates = \"12-01..12-31\"\npercent = 50\n\n\
+         [[schedule]]\nsource = \"monday\"\ncron = \"* * * * 1\"\n\n\
+         [[schedule]]\nsource = \"tech\"\ncron = \"* 9-17 * * 1-5\"\nweight = 2\n",
+    )
+    .expect("write config");
+
+    let list = |now: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+            .arg("--config")
+            .arg(&config)
+            .args(["--now", now, "-f"])
+            .arg(&dir)
+            .output()
+            .expect("run fortune -f");
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stderr)
+            .expect("stderr")
+            .lines()
+            .skip(1)
+            .map(|line| line.trim().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        list("2026-12-21T10:00"),
+        [
+            "50.00% holidays",
+            "12.50% monday",
+            "12.50% other",
+            "25.00% tech"
+        ]
+    );
+    assert_eq!(
+        list("2026-12-21T20:00"),
+        ["50.00% holidays", "25.00% monday", "25.00% other"]
+    );
+    assert_eq!(list("2026-10-18"), ["100.00% other"]);
+}
+
 #[test]
 fn deterministic_seed_matches_expected_selection() {
     let tmp = tempdir().expect("tempdir");
@@ -109,3 +335,671 @@ fn show_source_prints_banner_with_separator() {
     let expected = format!("({})\n%\nParsers should be strict.\n", alpha_abs.display());
     assert_eq!(stdout, expected);
 }
+
+#[test]
+fn auto_index_reads_unindexed_text_in_memory() {
+    let tmp = tempdir().expect("tempdir");
+    let alpha = tmp.path().join("alpha");
+    fs::write(
+        &alpha,
+        b"Rust keeps moving.\n%\nParsers should be strict.\n%\nLogs are your friend.\n",
+    )
+    .expect("write alpha");
+
+    let strict = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .arg(&alpha)
+        .output()
+        .expect("run fortune without auto-index");
+    assert!(!strict.status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .env("FORTUNE_MOD_RAND_HARD_CODED_VALS", "0")
+        .arg("--auto-index")
+        .arg(&alpha)
+        .output()
+        .expect("run fortune --auto-index");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).expect("stdout"),
+        "Parsers should be strict.\n"
+    );
+    assert!(!tmp.path().join("alpha.dat").exists());
+}
+
+#[test]
+fn auto_index_write_rebuilds_stale_dat() {
+    let tmp = tempdir().expect("tempdir");
+    let alpha = tmp.path().join("alpha");
+    write_indexed_file(&alpha, b"only one\n");
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(&alpha, b"first\n%\nsecond\n%\nthird\n").expect("edit alpha");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .env("FORTUNE_MOD_RAND_HARD_CODED_VALS", "0")
+        .arg("--auto-index=write")
+        .arg(&alpha)
+        .output()
+        .expect("run fortune --auto-index=write");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).expect("stdout"),
+        "second\n"
+    );
+
+    let dat = rustune::datfile::DatFile::read_from_path(&tmp.path().join("alpha.dat"))
+        .expect("read rewritten dat");
+    assert_eq!(dat.header.numstr, 3);
+}
+
+#[test]
+fn auto_index_skips_non_corpus_files_in_directories() {
+    let tmp = tempdir().expect("tempdir");
+    let dir = tmp.path().join("fortunes");
+    fs::create_dir(&dir).expect("mkdir");
+    fs::write(dir.join("jokes"), b"one\n%\ntwo\n").expect("write jokes");
+    fs::write(dir.join("README"), b"How to add fortunes.\n").expect("write readme");
+    fs::write(dir.join("Makefile"), b"all:\n\tstrfile jokes\n").expect("write makefile");
+    fs::write(dir.join("notes.md"), b"# Notes\n").expect("write notes");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .arg("--auto-index=write")
+        .arg("-f")
+        .arg(&dir)
+        .output()
+        .expect("run fortune --auto-index=write -f");
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).expect("stderr");
+    assert!(stderr.contains("100.00% jokes"), "{stderr}");
+    for skipped in ["README", "Makefile", "notes.md"] {
+        assert!(!stderr.contains(skipped), "{stderr}");
+    }
+    assert!(!dir.join("README.dat").exists());
+    assert!(!dir.join("Makefile.dat").exists());
+
+    fs::write(dir.join("jokes"), b"one\n%\ntwo\n%\nthree\n").expect("edit jokes");
+    fs::remove_file(dir.join("jokes.dat")).expect("remove dat");
+    let db = rustune::auto_index::open_fortune_file(
+        &dir.join("jokes"),
+        rustune::auto_index::AutoIndex::Memory,
+    )
+    .expect("open in memory");
+    assert_eq!(db.dat_path, None);
+}
+
+#[test]
+fn discovery_cache_matches_uncached_selection_and_invalidates() {
+    let tmp = tempdir().expect("tempdir");
+    let corpus = tmp.path().join("fortunes");
+    let cache_home = tmp.path().join("cache");
+    fs::create_dir(&corpus).expect("corpus dir");
+    write_indexed_file(
+        &corpus.join("alpha"),
+        b"Rust keeps moving.\n%\nParsers should be strict.\n%\nLogs are your friend.\n",
+    );
+
+    let run = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+            .env("FORTUNE_PATH", &corpus)
+            .env("XDG_CACHE_HOME", &cache_home)
+            .env_remove("LANG")
+            .env("FORTUNE_MOD_RAND_HARD_CODED_VALS", "4")
+            .args(extra)
+            .output()
+            .expect("run fortune");
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).expect("stdout")
+    };
+
+    let uncached = run(&["--no-cache"]);
+    assert_eq!(run(&[]), uncached);
+    let cache_file = cache_home.join("rustune").join("discovery.json");
+    assert!(cache_file.is_file());
+    assert_eq!(run(&[]), uncached);
+
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    write_indexed_file(&corpus.join("beta"), b"Small binaries, sharp tools.\n");
+    assert_eq!(run(&[]), run(&["--no-cache"]));
+    let cache = fs::read_to_string(&cache_file).expect("read cache");
+    assert!(cache.contains("beta"));
+}
+
+#[test]
+fn library_errors_map_to_stable_exit_codes() {
+    let tmp = tempdir().expect("tempdir");
+    let alpha = tmp.path().join("alpha");
+    fs::write(&alpha, b"unindexed\n").expect("write alpha");
+
+    let missing_dat = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .arg(&alpha)
+        .output()
+        .expect("run fortune on unindexed file");
+    assert_eq!(
+        missing_dat.status.code(),
+        Some(rustune::error::EXIT_NO_SOURCES)
+    );
+
+    let too_much = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .arg("60%")
+        .arg(&alpha)
+        .arg("50%")
+        .arg(&alpha)
+        .output()
+        .expect("run fortune with oversubscribed percentages");
+    assert_eq!(too_much.status.code(), Some(rustune::error::EXIT_USAGE));
+
+    let bad_regex = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .arg("--auto-index")
+        .arg("-m")
+        .arg("(")
+        .arg(&alpha)
+        .output()
+        .expect("run fortune with invalid regex");
+    assert_eq!(bad_regex.status.code(), Some(rustune::error::EXIT_USAGE));
+}
+
+#[cfg(feature = "builtin-corpus")]
+#[test]
+fn builtin_corpus_is_used_without_fortune_directories() {
+    let tmp = tempdir().expect("tempdir");
+    let missing = tmp.path().join("missing");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .env("FORTUNE_PATH", &missing)
+        .env("XDG_CACHE_HOME", tmp.path().join("cache"))
+        .env("FORTUNE_MOD_RAND_HARD_CODED_VALS", "0")
+        .arg("-c")
+        .output()
+        .expect("run fortune with builtin fallback");
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).expect("stdout");
+    assert!(stdout.starts_with("(builtin)\n%\n"));
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+            .current_dir(tmp.path())
+            .args(args)
+            .output()
+            .expect("run fortune builtin");
+        assert!(output.status.success(), "{output:?}");
+        output
+    };
+    assert_eq!(
+        String::from_utf8(run(&["-f", "builtin"]).stderr).expect("stderr"),
+        "100.00% builtin\n"
+    );
+
+    // A real corpus named `builtin` is not shadowed; `builtin:` still is the embedded one.
+    write_indexed_file(&tmp.path().join("builtin"), b"local\n");
+    assert_eq!(run(&["builtin"]).stdout, b"local\n");
+    assert_ne!(run(&["builtin:"]).stdout, b"local\n");
+}
+
+#[test]
+fn author_and_tag_filters_select_records_and_strip_metadata() {
+    let tmp = tempdir().expect("tempdir");
+    let quotes = tmp.path().join("quotes");
+    write_indexed_file(
+        &quotes,
+        b"#tags: humor\nTwo hard things.\n\t\t-- Phil Karlton\n%\nPremature optimization.\n\t\t-- Donald Knuth, TAOCP\n%\n#tags: humor, meta\nUntitled joke.\n",
+    );
+
+    let by_author = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--author", "knuth", "--no-attribution"])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune --author");
+    assert!(by_author.status.success(), "{by_author:?}");
+    assert_eq!(
+        String::from_utf8(by_author.stdout).expect("stdout"),
+        "Premature optimization.\n"
+    );
+
+    let by_tag = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--tag", "humor", "-m", "."])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune --tag");
+    assert!(by_tag.status.success(), "{by_tag:?}");
+    assert_eq!(
+        String::from_utf8(by_tag.stdout).expect("stdout"),
+        "Two hard things.\n\t\t-- Phil Karlton\n%\nUntitled joke.\n%\n"
+    );
+}
+
+#[test]
+fn tag_sidecar_drives_tag_filters_counts_and_percentages() {
+    let tmp = tempdir().expect("tempdir");
+    let quotes = tmp.path().join("quotes");
+    fs::write(
+        &quotes,
+        "#tags: humor\nTwo hard things.\n%\nPlain record.\n%\n#tags: humor, meta\nUntitled joke.\n",
+    )
+    .expect("write quotes");
+    let strfile = Command::new(env!("CARGO_BIN_EXE_strfile"))
+        .arg(&quotes)
+        .output()
+        .expect("run strfile");
+    assert!(strfile.status.success());
+    assert!(
+        String::from_utf8(strfile.stdout)
+            .expect("stdout")
+            .ends_with("2 tags indexed\n")
+    );
+
+    let filtered = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--tag", "humor", "--tag", "-meta", "-m", "."])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune --tag");
+    assert_eq!(
+        String::from_utf8(filtered.stdout).expect("stdout"),
+        "Two hard things.\n%\n"
+    );
+
+    let listing = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .arg("-f")
+        .arg("25%tag:humor")
+        .arg(&quotes)
+        .output()
+        .expect("run fortune -f");
+    assert!(listing.status.success(), "{listing:?}");
+    let quotes_abs = fs::canonicalize(&quotes).expect("quotes abs");
+    assert_eq!(
+        String::from_utf8(listing.stderr).expect("stderr"),
+        format!(
+            "75.00% {}\n    tags: humor (2), meta (1)\n25.00% tag:humor\n    tags: humor (2), meta (1)\n",
+            quotes_abs.display()
+        )
+    );
+
+    fs::write(
+        tmp.path().join("quotes.tags"),
+        r#"{"version":1,"tags":{"secret":[1]}}"#,
+    )
+    .expect("rewrite tag index");
+    let from_index = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--tag", "secret"])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune with tag index");
+    assert_eq!(
+        String::from_utf8(from_index.stdout).expect("stdout"),
+        "Plain record.\n"
+    );
+}
+
+#[test]
+fn tag_sidecar_is_not_discovered_as_a_corpus() {
+    let tmp = tempdir().expect("tempdir");
+    let dir = tmp.path().join("fortunes");
+    fs::create_dir(&dir).expect("mkdir");
+    let jokes = dir.join("jokes");
+    fs::write(&jokes, "#tags: humor\nKnock knock.\n").expect("write jokes");
+    let strfile = Command::new(env!("CARGO_BIN_EXE_strfile"))
+        .arg(&jokes)
+        .output()
+        .expect("run strfile");
+    assert!(strfile.status.success());
+    assert!(dir.join("jokes.tags").is_file());
+
+    let listing = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--auto-index=write", "-f"])
+        .arg(&dir)
+        .output()
+        .expect("run fortune --auto-index=write -f");
+    assert!(listing.status.success(), "{listing:?}");
+    let stderr = String::from_utf8(listing.stderr).expect("stderr");
+    assert!(stderr.contains("100.00% jokes"), "{stderr}");
+    assert!(!stderr.contains("jokes.tags"), "{stderr}");
+    assert!(!dir.join("jokes.tags.dat").exists());
+}
+
+#[test]
+fn wrap_and_max_lines_shape_output() {
+    let tmp = tempdir().expect("tempdir");
+    let quotes = tmp.path().join("quotes");
+    write_indexed_file(
+        &quotes,
+        b"A fairly long sentence that needs wrapping.\n%\nline one\nline two\nline three\n",
+    );
+
+    let wrapped = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--wrap=20", "-m", "sentence"])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune --wrap");
+    assert!(wrapped.status.success(), "{wrapped:?}");
+    assert_eq!(
+        String::from_utf8(wrapped.stdout).expect("stdout"),
+        "A fairly long\nsentence that needs\nwrapping.\n%\n"
+    );
+
+    let truncated = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--max-lines=2", "-m", "line"])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune --max-lines");
+    assert!(truncated.status.success(), "{truncated:?}");
+    assert_eq!(
+        String::from_utf8(truncated.stdout).expect("stdout"),
+        "line one\nline two …\n%\n"
+    );
+
+    let skipped = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--max-lines=1", "--overflow=skip"])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune --overflow=skip");
+    assert!(skipped.status.success(), "{skipped:?}");
+    assert_eq!(
+        String::from_utf8(skipped.stdout).expect("stdout"),
+        "A fairly long sentence that needs wrapping.\n"
+    );
+
+    // The same draw every time still finds the one record that fits.
+    let tall = tmp.path().join("tall");
+    let mut text = "one\ntwo\n%\n".repeat(50);
+    text.push_str("short\n");
+    write_indexed_file(&tall, text.as_bytes());
+    let fitting = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .env("FORTUNE_MOD_RAND_HARD_CODED_VALS", "0")
+        .args(["--max-lines=1", "--overflow=skip"])
+        .arg(&tall)
+        .output()
+        .expect("run fortune --overflow=skip");
+    assert!(fitting.status.success(), "{fitting:?}");
+    assert_eq!(fitting.stdout, b"short\n");
+
+    let zero = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .arg("--max-lines=0")
+        .arg(&quotes)
+        .output()
+        .expect("run fortune --max-lines=0");
+    assert_eq!(zero.status.code(), Some(2));
+}
+
+#[test]
+fn style_and_template_decorate_output() {
+    let tmp = tempdir().expect("tempdir");
+    let quotes = tmp.path().join("quotes");
+    write_indexed_file(&quotes, b"Be kind.\n\t\t-- Anon, Notes\n");
+
+    let boxed = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--style", "box", "--no-attribution"])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune --style box");
+    assert!(boxed.status.success(), "{boxed:?}");
+    assert_eq!(
+        String::from_utf8(boxed.stdout).expect("stdout"),
+        "┌──────────┐\n│ Be kind. │\n└──────────┘\n"
+    );
+
+    let quoted = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--style", "markdown"])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune --style markdown");
+    assert_eq!(
+        String::from_utf8(quoted.stdout).expect("stdout"),
+        "> Be kind.\n>\n> — *Anon, Notes*\n"
+    );
+
+    let templated = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--template", r"{body}\n  — {author} #{index}"])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune --template");
+    assert_eq!(
+        String::from_utf8(templated.stdout).expect("stdout"),
+        "Be kind.\n  — Anon #0\n"
+    );
+
+    for (flags, expected) in [
+        (&[][..], "Be kind. / Anon\n"),
+        (&["--no-attribution"][..], "Be kind. / \n"),
+    ] {
+        let templated = Command::new(env!("CARGO_BIN_EXE_rustune"))
+            .args(["--template", "{text} / {author}"])
+            .args(flags)
+            .arg(&quotes)
+            .output()
+            .expect("run fortune --template");
+        assert_eq!(
+            String::from_utf8(templated.stdout).expect("stdout"),
+            expected,
+            "{flags:?}"
+        );
+    }
+
+    let invalid = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--template", "{nope}"])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune --template");
+    assert!(!invalid.status.success());
+}
+
+#[test]
+fn wait_pacing_reads_config_and_cli_overrides() {
+    let tmp = tempdir().expect("tempdir");
+    let quotes = tmp.path().join("quotes");
+    write_indexed_file(&quotes, b"A short one.\n");
+    let config = tmp.path().join("config.toml");
+    fs::write(
+        &config,
+        "[wait]\nwpm = 1\nmin_seconds = 0\nmax_seconds = 30\n",
+    )
+    .expect("write config");
+
+    let started = std::time::Instant::now();
+    let paced = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["-w", "--max-wait", "0.2", "--config"])
+        .arg(&config)
+        .arg(&quotes)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("run fortune -w");
+    assert!(paced.status.success(), "{paced:?}");
+    assert_eq!(
+        String::from_utf8(paced.stdout).expect("stdout"),
+        "A short one.\n"
+    );
+    let elapsed = started.elapsed();
+    assert!(
+        elapsed >= std::time::Duration::from_millis(200),
+        "{elapsed:?}"
+    );
+    assert!(elapsed < std::time::Duration::from_secs(10), "{elapsed:?}");
+
+    fs::write(&config, "[wait]\nwmp = 1\n").expect("write config");
+    let invalid = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .arg("--config")
+        .arg(&config)
+        .arg(&quotes)
+        .output()
+        .expect("run fortune --config");
+    assert_eq!(invalid.status.code(), Some(2), "{invalid:?}");
+
+    let bad_waits: [&[&str]; 4] = [
+        &["--min-wait", "NaN"],
+        &["--max-wait", "-1"],
+        &["--min-wait", "5", "--max-wait", "1"],
+        &["--wpm", "0"],
+    ];
+    for args in bad_waits {
+        let invalid = Command::new(env!("CARGO_BIN_EXE_rustune"))
+            .arg("-w")
+            .args(args)
+            .arg(&quotes)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .expect("run fortune -w");
+        assert_eq!(invalid.status.code(), Some(2), "{args:?}: {invalid:?}");
+        assert!(invalid.stdout.is_empty(), "{args:?}");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn loop_mode_cycles_without_repeats_and_stops_on_sigterm() {
+    let tmp = tempdir().expect("tempdir");
+    let quotes = tmp.path().join("quotes");
+    write_indexed_file(&quotes, b"one\n%\ntwo\n%\nthree\n");
+
+    let child = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--loop", "--interval", "0.05"])
+        .arg(&quotes)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .expect("spawn fortune --loop");
+    std::thread::sleep(std::time::Duration::from_millis(600));
+    // SAFETY: signals a child process we spawned and still own.
+    unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) };
+    let output = child.wait_with_output().expect("wait for loop");
+    assert!(output.status.success(), "{output:?}");
+
+    let stdout = String::from_utf8(output.stdout).expect("stdout");
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines.len() >= 6, "{lines:?}");
+    for round in lines.chunks(3).filter(|round| round.len() == 3) {
+        let mut round = round.to_vec();
+        round.sort_unstable();
+        assert_eq!(round, ["one", "three", "two"]);
+    }
+
+    for interval in ["0", "NaN", "-1", "inf"] {
+        let invalid = Command::new(env!("CARGO_BIN_EXE_rustune"))
+            .args(["--loop", "--interval", interval])
+            .arg(&quotes)
+            .output()
+            .expect("run fortune --interval");
+        assert_eq!(invalid.status.code(), Some(2), "{interval}: {invalid:?}");
+        assert!(invalid.stdout.is_empty());
+    }
+}
+
+#[test]
+fn count_prints_unique_batches_separated_by_delimiters() {
+    let tmp = tempdir().expect("tempdir");
+    let quotes = tmp.path().join("quotes");
+    write_indexed_file(&quotes, b"one\n%\ntwo\n%\nthree\n");
+
+    let unique = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["-N", "5"])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune -N");
+    assert!(unique.status.success(), "{unique:?}");
+    let stdout = String::from_utf8(unique.stdout).expect("stdout");
+    let mut records: Vec<&str> = stdout.split("%\n").map(str::trim_end).collect();
+    records.sort_unstable();
+    assert_eq!(records, ["one", "three", "two"]);
+
+    let replaced = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["-N", "5", "--with-replacement"])
+        .arg(&quotes)
+        .output()
+        .expect("run fortune -N --with-replacement");
+    assert!(replaced.status.success(), "{replaced:?}");
+    let stdout = String::from_utf8(replaced.stdout).expect("stdout");
+    assert_eq!(stdout.split("%\n").count(), 5);
+}
+
+#[test]
+fn simulate_reports_expected_and_observed_frequencies() {
+    let tmp = tempdir().expect("tempdir");
+    let alpha = tmp.path().join("alpha");
+    let beta = tmp.path().join("beta");
+    write_indexed_file(&alpha, b"one\n%\ntwo\n");
+    write_indexed_file(&beta, b"three\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--simulate", "2000", "75%"])
+        .arg(&alpha)
+        .arg(&beta)
+        .output()
+        .expect("run fortune --simulate");
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).expect("stdout");
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "2000 draws");
+    assert!(lines[2].starts_with("   75.00% "), "{stdout}");
+    assert!(
+        lines[2].ends_with(&format!("  {}", alpha.display())),
+        "{stdout}"
+    );
+    assert!(lines[3].starts_with("   37.50% "), "{stdout}");
+    assert!(lines[5].starts_with("   25.00% "), "{stdout}");
+    assert!(lines[7].starts_with("sources: chi-square "), "{stdout}");
+    assert!(lines[8].contains("(df 2)"), "{stdout}");
+}
+
+#[test]
+fn fair_record_selection_comes_from_config_or_flag() {
+    let tmp = tempdir().expect("tempdir");
+    let file = tmp.path().join("gappy");
+    write_indexed_file(&file, b"a\n%\nlong long\n%\nlong long\n%\nb\n");
+    let config = tmp.path().join("config.toml");
+    fs::write(&config, "[selection]\nrecords = \"fair\"\n").expect("write config");
+
+    let expected_shares = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+            .arg("--config")
+            .arg(&config)
+            .args(extra)
+            .args(["--simulate", "100", "-s", "-n", "3"])
+            .arg(&file)
+            .output()
+            .expect("run fortune --simulate");
+        assert!(output.status.success(), "{output:?}");
+        let stdout = String::from_utf8(output.stdout).expect("stdout");
+        stdout
+            .lines()
+            .skip(3)
+            .take(2)
+            .map(|line| line.split_whitespace().next().unwrap_or("").to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(expected_shares(&[]), ["50.00%", "50.00%"]);
+    assert_eq!(
+        expected_shares(&["--record-selection", "parity"]),
+        ["25.00%", "75.00%"]
+    );
+}
+
+#[test]
+fn explain_json_reports_skipped_sources_and_draws() {
+    let tmp = tempdir().expect("tempdir");
+    write_indexed_file(
+        &tmp.path().join("alpha"),
+        b"a\n%\nlong enough record\n%\nc\n",
+    );
+    write_indexed_file(&tmp.path().join("beta-o"), b"x\n");
+    fs::write(tmp.path().join("notes"), "no index here\n").expect("write notes");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rustune"))
+        .args(["--explain=json", "-s", "-n", "5"])
+        .arg(tmp.path())
+        .output()
+        .expect("run fortune --explain");
+    assert!(output.status.success(), "{output:?}");
+    let explanation: serde_json::Value =
+        serde_json::from_slice(&output.stderr).expect("explain json");
+    let statuses: Vec<&str> = explanation["sources"]
+        .as_array()
+        .expect("sources")
+        .iter()
+        .map(|source| source["status"].as_str().expect("status"))
+        .collect();
+    assert_eq!(statuses, ["loaded", "offensive", "no-dat"]);
+    assert_eq!(explanation["mode"], "candidate-count");
+    assert_eq!(explanation["probabilities"][0]["candidates"], 2);
+    let draw = &explanation["draw"];
+    assert_eq!(draw["record_total"], 3);
+    let record = draw["record_index"].as_u64().expect("record index");
+    assert!(record == 0 || record == 2, "{draw}");
+    let stdout = String::from_utf8(output.stdout).expect("stdout");
+    assert_eq!(stdout, if record == 0 { "a\n" } else { "c\n" });
+}